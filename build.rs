@@ -0,0 +1,117 @@
+/****************************************************************/
+//                            Uses                              //
+/****************************************************************/
+
+use std::{
+    env,
+    fs,
+    path::Path
+};
+
+/****************************************************************/
+//                         Constants                            //
+/****************************************************************/
+
+/* Path to `nm -n --demangle` output of the previous kernel build */
+const SYMBOLS_VARIABLE: &str = "OS_SYMBOLS";
+
+/* Path to `nm -n --demangle` output of the build made with `OS_SYMBOLS`, checked against it */
+const CHECK_VARIABLE: &str = "OS_SYMBOLS_CHECK";
+
+/* Bytes reserved for the table whether it is filled or not */
+const TABLE_SIZE: usize = 0x60000;
+
+/* Count of symbols in front of the entries */
+const HEADER_SIZE: usize = 8;
+
+/* Address, offset of the name in the table and length of the name */
+const ENTRY_SIZE: usize = 16;
+
+/****************************************************************/
+//                     Other functions                          //
+/****************************************************************/
+
+/* Turns lines like `ffff800000001234 T os::init` into `(0x..., "os::init")` for text symbols */
+fn parse(nm: &str) -> Vec <(u64, String)> {
+    let mut symbols: Vec <(u64, String)> = nm.lines().filter_map(|line| {
+        let mut fields = line.splitn(3, ' ');
+        let address = u64::from_str_radix(fields.next()?, 16).ok()?;
+        match fields.next()? {
+            "T" | "t" | "W" | "w" => Some((address, fields.next()?.to_string())),
+            _ => None
+        }
+    }).collect();
+    symbols.sort_by_key(|&(address, _)| address);
+    symbols.dedup_by_key(|&mut (address, _)| address);
+    symbols
+}
+
+/* Packs as many symbols as fit into `TABLE_SIZE` bytes: the header, the entries sorted by address, the names */
+fn pack(symbols: &[(u64, String)]) -> Vec <u8> {
+    let mut fitting = 0;
+    let mut names = 0;
+    for (_, name) in symbols {
+        if HEADER_SIZE + (fitting + 1) * ENTRY_SIZE + names + name.len() > TABLE_SIZE { break }
+        fitting += 1;
+        names += name.len();
+    }
+    if fitting < symbols.len() {
+        println!("cargo:warning={} of {} symbols do not fit into the backtrace table", symbols.len() - fitting, symbols.len());
+    }
+
+    let mut table = vec![0u8; TABLE_SIZE];
+    table[..HEADER_SIZE].copy_from_slice(&(fitting as u64).to_le_bytes());
+    let mut name_offset = HEADER_SIZE + fitting * ENTRY_SIZE;
+    for (index, (address, name)) in symbols[..fitting].iter().enumerate() {
+        let entry = HEADER_SIZE + index * ENTRY_SIZE;
+        table[entry..entry + 8].copy_from_slice(&address.to_le_bytes());
+        table[entry + 8..entry + 12].copy_from_slice(&(name_offset as u32).to_le_bytes());
+        table[entry + 12..entry + 16].copy_from_slice(&(name.len() as u32).to_le_bytes());
+        table[name_offset..name_offset + name.len()].copy_from_slice(name.as_bytes());
+        name_offset += name.len();
+    }
+    table
+}
+
+/* Panics on the first symbol that has another address in `check` than in `symbols`,
+   i.e. when filling the table has moved the code it describes */
+fn check(symbols: &[(u64, String)], check: &[(u64, String)]) {
+    for ((address, name), (moved, moved_name)) in symbols.iter().zip(check.iter()) {
+        if address != moved || name != moved_name {
+            panic!("Symbols moved between the builds: {} at 0x{:x} is now {} at 0x{:x}, build both passes again", name, address, moved_name, moved);
+        }
+    }
+    if symbols.len() != check.len() {
+        panic!("Symbols changed between the builds: {} before, {} now, build both passes again", symbols.len(), check.len());
+    }
+}
+
+fn read_symbols(variable: &str) -> Option <Vec <(u64, String)>> {
+    println!("cargo:rerun-if-env-changed={}", variable);
+    let path = env::var(variable).ok()?;
+    println!("cargo:rerun-if-changed={}", path);
+    Some(parse(&fs::read_to_string(&path).expect("Can not read symbols file")))
+}
+
+/* Writes `SYMBOL_TABLE` for `backtrace`, only with the `backtrace` feature.
+   Addresses come from the previous build, see README for the two passes and the check.
+   The table has the same size in both passes and `backtrace` reads it through an opaque pointer,
+   so filling it should move no code; `OS_SYMBOLS_CHECK` makes sure it did not */
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    if env::var_os("CARGO_FEATURE_BACKTRACE").is_none() { return }
+
+    let symbols = read_symbols(SYMBOLS_VARIABLE).unwrap_or_default();
+    if let Some(moved) = read_symbols(CHECK_VARIABLE) { check(&symbols, &moved) }
+
+    let out = env::var("OUT_DIR").unwrap();
+    fs::write(Path::new(&out).join("symbols.bin"), pack(&symbols)).unwrap();
+
+    let mut source = String::new();
+    source += &format!("pub const SYMBOL_TABLE_SIZE: usize = 0x{:x};\n", TABLE_SIZE);
+    source += &format!("pub const SYMBOL_HEADER_SIZE: usize = {};\n", HEADER_SIZE);
+    source += &format!("pub const SYMBOL_ENTRY_SIZE: usize = {};\n", ENTRY_SIZE);
+    source += "#[link_section = \".rodata.symbols\"]\n";
+    source += "pub static SYMBOL_TABLE: [u8; SYMBOL_TABLE_SIZE] = *include_bytes!(concat!(env!(\"OUT_DIR\"), \"/symbols.bin\"));\n";
+    fs::write(Path::new(&out).join("symbols.rs"), source).unwrap();
+}
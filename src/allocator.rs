@@ -11,7 +11,11 @@ mod private {
         VirtAddr,
     };
     use alloc::alloc::{Layout, GlobalAlloc};
-    use core::{ptr, mem};
+    use core::{
+        ptr,
+        mem,
+        sync::atomic::{AtomicUsize, Ordering}
+    };
 
     /****************************************************************/
     //                         Constants                            //
@@ -76,7 +80,7 @@ mod private {
     unsafe impl GlobalAlloc for Locked <FixedSizeBlockAllocator> {
         unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
             let mut allocator = self.lock();
-            match list_index(&layout) {
+            let ptr = match list_index(&layout) {
                 Some(index) => {
                     match allocator.list[index].take() {
                         Some(node) => {
@@ -92,11 +96,14 @@ mod private {
                     }
                 }
                 None => allocator.fallback_alloc(layout)
-            }
+            };
+            if !ptr.is_null() { USED.fetch_add(allocated_size(&layout), Ordering::Relaxed); }
+            ptr
         }
 
         unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
             let mut allocator = self.lock();
+            USED.fetch_sub(allocated_size(&layout), Ordering::Relaxed);
             match list_index(&layout) {
                 Some(index) => {
                     let new_node = Node {
@@ -123,6 +130,9 @@ mod private {
     #[global_allocator]
     static ALLOCATOR: Locked <FixedSizeBlockAllocator> = Locked::new(FixedSizeBlockAllocator::new());
 
+    /* Bytes taken from the heap, blocks count whole */
+    static USED: AtomicUsize = AtomicUsize::new(0);
+
     /****************************************************************/
     //                     Other functions                          //
     /****************************************************************/
@@ -132,6 +142,14 @@ mod private {
         BLOCK_SIZES.iter().position(|&s| s >= required)
     }
 
+    fn allocated_size(layout: &Layout) -> usize {
+        list_index(layout).map_or(layout.size(), |index| BLOCK_SIZES[index])
+    }
+
+    pub fn used() -> usize {
+        USED.load(Ordering::Relaxed)
+    }
+
     pub fn init_heap(mapper: &mut impl Mapper <Size4KiB>, frame_allocator: &mut impl FrameAllocator <Size4KiB>) -> Result <(), MapToError <Size4KiB>> {
         let range = {
             let start = VirtAddr::new(HEAP_START as u64);
@@ -0,0 +1,172 @@
+/****************************************************************/
+//                            Uses                              //
+/****************************************************************/
+
+use core::fmt;
+use spin::Mutex;
+use x86_64::structures::idt::InterruptStackFrame;
+
+/* Generated by build.rs for the `backtrace` feature only, `SYMBOL_TABLE` is empty unless `OS_SYMBOLS` was set */
+include!(concat!(env!("OUT_DIR"), "/symbols.rs"));
+
+/****************************************************************/
+//                         Constants                            //
+/****************************************************************/
+
+pub const MAX_FRAMES: usize = 32;
+
+/* Frames further apart than this are treated as garbage */
+const MAX_FRAME_SIZE: u64 = 0x10_0000;
+
+/****************************************************************/
+//                            Types                             //
+/****************************************************************/
+
+/* What `rbp` points to when frame pointers are kept */
+#[repr(C)]
+struct Frame {
+    next: *const Frame,
+    ret: u64
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Backtrace {
+    frames: [u64; MAX_FRAMES],
+    count: usize
+}
+
+impl Backtrace {
+    const fn new() -> Self {
+        Backtrace {
+            frames: [0; MAX_FRAMES],
+            count: 0
+        }
+    }
+
+    /* Backtrace of the caller */
+    #[inline(always)]
+    pub fn capture() -> Self {
+        let rbp: u64;
+        let rsp: u64;
+        unsafe {
+            asm!("mov {}, rbp", out(reg) rbp);
+            asm!("mov {}, rsp", out(reg) rsp);
+        }
+        let mut backtrace = Self::new();
+        unsafe { backtrace.walk(rbp, rsp) };
+        backtrace
+    }
+
+    /* Backtrace of the interrupted code, must be called right in the exception handler */
+    #[inline(always)]
+    pub fn exception(isf: &InterruptStackFrame) -> Self {
+        /* Handler's frame keeps `rbp` of the interrupted code */
+        let rbp: u64;
+        unsafe { asm!("mov {}, [rbp]", out(reg) rbp) };
+        let mut backtrace = Self::new();
+        backtrace.push(isf.instruction_pointer.as_u64());
+        unsafe { backtrace.walk(rbp, isf.stack_pointer.as_u64()) };
+        backtrace
+    }
+
+    fn push(&mut self, address: u64) {
+        if self.count < MAX_FRAMES {
+            self.frames[self.count] = address;
+            self.count += 1;
+        }
+    }
+
+    /* Follows saved `rbp`s; frames must lie above `floor` and grow towards the bottom of the stack */
+    unsafe fn walk(&mut self, rbp: u64, floor: u64) {
+        let mut frame = rbp as *const Frame;
+        let mut floor = floor;
+        while self.count < MAX_FRAMES {
+            let address = frame as u64;
+            if address == 0 || address % 8 != 0 || address < floor || address - floor > MAX_FRAME_SIZE { break }
+            let ret = (*frame).ret;
+            if ret == 0 { break }
+            self.push(ret);
+            floor = address + 16;
+            frame = (*frame).next;
+        }
+    }
+
+    pub fn frames(&self) -> &[u64] {
+        &self.frames[..self.count]
+    }
+}
+
+impl fmt::Display for Backtrace {
+    fn fmt(&self, f: &mut fmt::Formatter <'_>) -> fmt::Result {
+        for (n, &address) in self.frames().iter().enumerate() {
+            match resolve(address) {
+                Some((name, offset)) => writeln!(f, "#{} 0x{:016x} {}+0x{:x}", n, address, name, offset)?,
+                None => writeln!(f, "#{} 0x{:016x} <unknown>", n, address)?
+            }
+        }
+        Ok(())
+    }
+}
+
+/****************************************************************/
+//                           Statics                            //
+/****************************************************************/
+
+/* Set by exception handlers, so the panic handler prints the interrupted code instead of itself */
+static EXCEPTION: Mutex <Option <Backtrace>> = Mutex::new(None);
+
+/****************************************************************/
+//                     Other functions                          //
+/****************************************************************/
+
+/* The table is reached through a volatile read of its address, so the compiler can not look into it
+   and the code stays the same whatever the table holds */
+fn symbol_table() -> &'static [u8] {
+    let table: *const [u8; SYMBOL_TABLE_SIZE] = &SYMBOL_TABLE;
+    unsafe { &*core::ptr::read_volatile(&table) }
+}
+
+fn read_u32(table: &[u8], offset: usize) -> u32 {
+    let mut bytes = [0; 4];
+    bytes.copy_from_slice(&table[offset..offset + 4]);
+    u32::from_le_bytes(bytes)
+}
+
+fn read_u64(table: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&table[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+/* Start address and name of symbol `index` */
+fn symbol(table: &'static [u8], index: usize) -> (u64, &'static str) {
+    let entry = SYMBOL_HEADER_SIZE + index * SYMBOL_ENTRY_SIZE;
+    let start = read_u32(table, entry + 8) as usize;
+    let end = (start + read_u32(table, entry + 12) as usize).min(table.len());
+    let name = table.get(start..end).and_then(|name| core::str::from_utf8(name).ok()).unwrap_or("<invalid>");
+    (read_u64(table, entry), name)
+}
+
+/* Returns function containing `address` and offset in it */
+pub fn resolve(address: u64) -> Option <(&'static str, u64)> {
+    let table = symbol_table();
+    let count = (read_u64(table, 0) as usize).min((SYMBOL_TABLE_SIZE - SYMBOL_HEADER_SIZE) / SYMBOL_ENTRY_SIZE);
+    /* Symbols starting at or before `address` come before `low` */
+    let (mut low, mut high) = (0, count);
+    while low < high {
+        let middle = (low + high) / 2;
+        if symbol(table, middle).0 <= address { low = middle + 1 } else { high = middle }
+    }
+    if low == 0 { return None }
+    let (start, name) = symbol(table, low - 1);
+    Some((name, address - start))
+}
+
+pub fn set_exception(backtrace: Backtrace) {
+    *EXCEPTION.lock() = Some(backtrace);
+}
+
+/* Never waits, it is called from the panic handler */
+pub fn take_exception() -> Option <Backtrace> {
+    EXCEPTION.try_lock().and_then(|mut exception| exception.take())
+}
@@ -0,0 +1,232 @@
+/****************************************************************/
+//                            Uses                              //
+/****************************************************************/
+
+use alloc::{
+    boxed::Box,
+    sync::Arc,
+    vec::Vec
+};
+use core::{
+    pin::Pin,
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+    task::{Context, Poll}
+};
+use conquer_once::spin::OnceCell;
+use crossbeam_queue::ArrayQueue;
+use futures_util::{
+    stream::Stream,
+    task::AtomicWaker
+};
+use spin::Mutex;
+use x86_64::instructions::interrupts;
+
+/****************************************************************/
+//                         Constants                            //
+/****************************************************************/
+
+/* Handlers with a higher priority see events first */
+pub const PRIORITY_HIGH: Priority = 100;
+pub const PRIORITY_NORMAL: Priority = 0;
+pub const PRIORITY_LOW: Priority = -100;
+
+/* Events beyond this are dropped until someone reads */
+pub const QUEUE_CAPACITY: usize = 128;
+
+/****************************************************************/
+//                            Types                             //
+/****************************************************************/
+
+pub type Priority = i32;
+
+/* What a handler wants done with the event it has seen */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Propagation {
+    Continue, //< Pass the event on to handlers with lower priority
+    Stop      //< The event is consumed
+}
+
+pub trait Handler <E>: Send {
+    fn handle(&mut self, event: E) -> Propagation;
+}
+
+/* Closures keep their captured state between events */
+impl <E, F: FnMut(E) -> Propagation + Send> Handler <E> for F {
+    fn handle(&mut self, event: E) -> Propagation {
+        self(event)
+    }
+}
+
+type Shared <E> = Arc <Mutex <Box <dyn Handler <E>>>>;
+
+struct Entry <E> {
+    id: u64,
+    priority: Priority,
+    handler: Shared <E>
+}
+
+/* Handlers of one kind of events, e.g. of the keyboard or the mouse */
+pub struct Handlers <E: 'static> {
+    /* Sorted by priority, the earlier registered first among equal ones */
+    entries: Mutex <Vec <Entry <E>>>,
+    next_id: AtomicU64
+}
+
+impl <E: Copy + 'static> Handlers <E> {
+    pub const fn new() -> Self {
+        Handlers {
+            entries: Mutex::new(Vec::new()),
+            next_id: AtomicU64::new(0)
+        }
+    }
+
+    pub fn register(&'static self, priority: Priority, handler: impl Handler <E> + 'static) -> HandlerHandle <E> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let handler: Shared <E> = Arc::new(Mutex::new(Box::new(handler)));
+        interrupts::without_interrupts(|| {
+            let mut entries = self.entries.lock();
+            let index = entries.iter().position(|entry| entry.priority < priority).unwrap_or(entries.len());
+            entries.insert(index, Entry { id, priority, handler });
+        });
+        HandlerHandle { handlers: self, id }
+    }
+
+    /* Runs the handlers on `event` until one consumes it, returns whether one did.
+       The list is copied first, so handlers may register and unregister handlers themselves;
+       a handler that is already running, i.e. reads events from inside itself, is skipped */
+    pub fn dispatch(&self, event: E) -> bool {
+        let handlers: Vec <Shared <E>> = interrupts::without_interrupts(|| self.entries.lock().iter().map(|entry| entry.handler.clone()).collect());
+        for handler in handlers {
+            let propagation = match handler.try_lock() {
+                Some(mut handler) => handler.handle(event),
+                None => continue
+            };
+            if propagation == Propagation::Stop { return true }
+        }
+        false
+    }
+
+    fn unregister(&self, id: u64) {
+        /* Taken out under the lock but dropped after it, the handler may own anything */
+        let entry = interrupts::without_interrupts(|| {
+            let mut entries = self.entries.lock();
+            let index = entries.iter().position(|entry| entry.id == id)?;
+            Some(entries.remove(index))
+        });
+        drop(entry);
+    }
+}
+
+/* Unregisters its handler when dropped */
+#[must_use = "the handler is unregistered as soon as the handle is dropped"]
+pub struct HandlerHandle <E: Copy + 'static> {
+    handlers: &'static Handlers <E>,
+    id: u64
+}
+
+impl <E: Copy + 'static> HandlerHandle <E> {
+    /* Keeps the handler registered for good */
+    pub fn forget(self) {
+        core::mem::forget(self)
+    }
+}
+
+impl <E: Copy + 'static> Drop for HandlerHandle <E> {
+    fn drop(&mut self) {
+        self.handlers.unregister(self.id);
+    }
+}
+
+impl <E: Copy + 'static> core::fmt::Debug for HandlerHandle <E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter <'_>) -> core::fmt::Result {
+        f.debug_struct("HandlerHandle").field("id", &self.id).finish()
+    }
+}
+
+/* Events queued by an IRQ until a reader takes them, every event is passed through the handlers then */
+pub struct Events <E: 'static> {
+    queue: OnceCell <ArrayQueue <E>>,
+    waker: AtomicWaker,
+    dropped: AtomicUsize,
+    handlers: &'static Handlers <E>
+}
+
+impl <E: Copy + Send + 'static> Events <E> {
+    pub const fn new(handlers: &'static Handlers <E>) -> Self {
+        Events {
+            queue: OnceCell::uninit(),
+            waker: AtomicWaker::new(),
+            dropped: AtomicUsize::new(0),
+            handlers
+        }
+    }
+
+    /* Allocates the queue, events pushed before are dropped */
+    pub fn init(&self) {
+        self.queue.try_init_once(|| ArrayQueue::new(QUEUE_CAPACITY)).ok();
+    }
+
+    /* Called from the IRQ, never blocks */
+    pub fn push(&self, event: E) {
+        match self.queue.try_get() {
+            Ok(queue) if queue.push(event).is_ok() => self.waker.wake(),
+            _ => { self.dropped.fetch_add(1, Ordering::Relaxed); }
+        }
+    }
+
+    fn pop(&self) -> Option <E> {
+        self.queue.try_get().ok()?.pop().ok()
+    }
+
+    /* Returns the oldest event after running the handlers on it, or `None` if there is none yet */
+    pub fn try_next(&self) -> Option <E> {
+        let event = self.pop()?;
+        self.handlers.dispatch(event);
+        Some(event)
+    }
+
+    /* Waits for an event and runs the handlers on it */
+    pub fn next(&self) -> E {
+        loop {
+            /* Interrupts are off between the check and `hlt`, so an event coming in between wakes us up */
+            interrupts::disable();
+            if let Some(event) = self.pop() {
+                interrupts::enable();
+                self.handlers.dispatch(event);
+                return event
+            }
+            interrupts::enable_and_hlt();
+        }
+    }
+
+    /* How many events were lost because the queue was full */
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    pub fn stream(&'static self) -> EventStream <E> {
+        EventStream { events: self }
+    }
+}
+
+/* Asynchronous source of events, every event is passed through the handlers before being yielded */
+pub struct EventStream <E: 'static> {
+    events: &'static Events <E>
+}
+
+impl <E: Copy + Send + 'static> Stream for EventStream <E> {
+    type Item = E;
+
+    fn poll_next(self: Pin <&mut Self>, cx: &mut Context) -> Poll <Option <E>> {
+        if let Some(event) = self.events.try_next() { return Poll::Ready(Some(event)) }
+        self.events.waker.register(cx.waker());
+        match self.events.try_next() {
+            Some(event) => {
+                self.events.waker.take();
+                Poll::Ready(Some(event))
+            },
+            None => Poll::Pending
+        }
+    }
+}
@@ -6,7 +6,6 @@ mod private {
 
     use crate::{
         enum_flags,
-        println,
         time::{sleep, Time},
     };
     use x86_64::instructions::port::Port;
@@ -554,7 +553,7 @@ mod private {
         }
         for i in 0..4 {
             if DEVICES[i].reserved {
-                println!("Found {:?} Drive ({} bytes) at {}.{} - {}", DEVICES[i].r#type, DEVICES[i].size, DEVICES[i].channel as u8, DEVICES[i].drive as u8, String::from_utf8(Vec::from(DEVICES[i].model)).unwrap().as_str())
+                crate::log_or_println!("Found {:?} Drive ({} bytes) at {}.{} - {}", DEVICES[i].r#type, DEVICES[i].size, DEVICES[i].channel as u8, DEVICES[i].drive as u8, String::from_utf8(Vec::from(DEVICES[i].model)).unwrap().as_str());
             }
         }
     }
@@ -6,7 +6,8 @@ use x86_64::{
     structures::idt::{
         InterruptDescriptorTable, InterruptStackFrame, PageFaultErrorCode
     },
-    registers::control::Cr2
+    registers::control::Cr2,
+    instructions::port::Port
 };
 use pic8259::ChainedPics;
 use spin;
@@ -18,9 +19,18 @@ use crate::{
 #[cfg(all(feature = "keyboard", feature = "allocator"))]
 use crate::keyboard::keyboard_isr;
 
+#[cfg(all(feature = "mouse", feature = "allocator"))]
+use crate::mouse::mouse_isr;
+
 #[cfg(feature = "time")]
 use crate::time::timer_isr;
 
+#[cfg(feature = "time")]
+use crate::rtc::rtc_isr;
+
+#[cfg(feature = "serial")]
+use crate::serial::{com1_isr, com2_isr};
+
 /****************************************************************/
 //                         Constants                            //
 /****************************************************************/
@@ -28,6 +38,9 @@ use crate::time::timer_isr;
 pub const PIC1: u8 = 0x20;
 pub const PIC2: u8 = PIC1 + 8;
 
+pub const PIC1_DATA_PORT: u16 = 0x21;
+pub const PIC2_DATA_PORT: u16 = 0xA1;
+
 /****************************************************************/
 //                            Types                             //
 /****************************************************************/
@@ -36,7 +49,11 @@ pub const PIC2: u8 = PIC1 + 8;
 #[repr(u8)]
 pub enum InterruptIndex {
     Timer = PIC1,
-    Keyboard
+    Keyboard,
+    Com2 = PIC1 + 3,
+    Com1,
+    Rtc = PIC2,
+    Mouse = PIC2 + 4
 }
 
 /****************************************************************/
@@ -48,6 +65,16 @@ macro_rules! irq_end {
     ($index:expr) => { unsafe { PICS.lock().notify_end_of_interrupt($index as u8) } };
 }
 
+/* Keeps the backtrace of the interrupted code for the panic handler, must be used right in the handler */
+macro_rules! record_exception {
+    ($isf:expr) => {
+        #[cfg(feature = "backtrace")]
+        crate::backtrace::set_exception(crate::backtrace::Backtrace::exception(&$isf));
+        #[cfg(not(feature = "backtrace"))]
+        let _ = &$isf;
+    };
+}
+
 /****************************************************************/
 //                           Statics                            //
 /****************************************************************/
@@ -104,6 +131,14 @@ lazy_static! {
 
         idt[InterruptIndex::Keyboard as usize].set_handler_fn(keyboard);
 
+        idt[InterruptIndex::Com2 as usize].set_handler_fn(com2);
+
+        idt[InterruptIndex::Com1 as usize].set_handler_fn(com1);
+
+        idt[InterruptIndex::Rtc as usize].set_handler_fn(rtc);
+
+        idt[InterruptIndex::Mouse as usize].set_handler_fn(mouse);
+
         /* Other */
 
         idt
@@ -118,13 +153,36 @@ pub fn init() {
     IDT.load();
 }
 
+/* Allows PICs to deliver IRQ `irq`(0..16) */
+pub fn unmask(irq: u8) {
+    let (port, bit) = if irq < 8 { (PIC1_DATA_PORT, irq) } else { (PIC2_DATA_PORT, irq - 8) };
+    let mut port = Port::<u8>::new(port);
+    unsafe {
+        let mask = port.read();
+        port.write(mask & !(1 << bit));
+    }
+    /* IRQs of the slave PIC come through the cascade line */
+    if irq >= 8 { unmask(2) }
+}
+
+pub fn mask(irq: u8) {
+    let (port, bit) = if irq < 8 { (PIC1_DATA_PORT, irq) } else { (PIC2_DATA_PORT, irq - 8) };
+    let mut port = Port::<u8>::new(port);
+    unsafe {
+        let mask = port.read();
+        port.write(mask | (1 << bit));
+    }
+}
+
 /****************************************************************/
 //                            IRQs                              //
 /****************************************************************/
 
-extern "x86-interrupt" fn keyboard(_isf: InterruptStackFrame) {
+extern "x86-interrupt" fn keyboard(isf: InterruptStackFrame) {
     #[cfg(all(feature = "keyboard", feature = "allocator"))]
-    keyboard_isr();
+    keyboard_isr(&isf);
+    #[cfg(not(all(feature = "keyboard", feature = "allocator")))]
+    let _ = isf;
 
     irq_end!(InterruptIndex::Keyboard);
 }
@@ -134,9 +192,42 @@ extern "x86-interrupt" fn timer(_isf: InterruptStackFrame) {
     #[cfg(feature = "time")]
     timer_isr();
 
+    #[cfg(all(feature = "keyboard", feature = "allocator"))]
+    crate::keyboard::timer_isr();
+
+    crate::tty::status::timer_isr();
+
     irq_end!(InterruptIndex::Timer);
 }
 
+extern "x86-interrupt" fn com2(_isf: InterruptStackFrame) {
+    #[cfg(feature = "serial")]
+    com2_isr();
+
+    irq_end!(InterruptIndex::Com2);
+}
+
+extern "x86-interrupt" fn com1(_isf: InterruptStackFrame) {
+    #[cfg(feature = "serial")]
+    com1_isr();
+
+    irq_end!(InterruptIndex::Com1);
+}
+
+extern "x86-interrupt" fn rtc(_isf: InterruptStackFrame) {
+    #[cfg(feature = "time")]
+    rtc_isr();
+
+    irq_end!(InterruptIndex::Rtc);
+}
+
+extern "x86-interrupt" fn mouse(_isf: InterruptStackFrame) {
+    #[cfg(all(feature = "mouse", feature = "allocator"))]
+    mouse_isr();
+
+    irq_end!(InterruptIndex::Mouse);
+}
+
 /****************************************************************/
 //                            ISRs                              //
 /****************************************************************/
@@ -145,83 +236,103 @@ extern "x86-interrupt" fn timer(_isf: InterruptStackFrame) {
 //                           basic                              //
 /****************************************************************/
 
-extern "x86-interrupt" fn divide0(_isf: InterruptStackFrame) {
+extern "x86-interrupt" fn divide0(isf: InterruptStackFrame) {
+    record_exception!(isf);
     panic!("Divide by zero occurred!")
 }
 
-extern "x86-interrupt" fn debug(_isf: InterruptStackFrame) {
+extern "x86-interrupt" fn debug(isf: InterruptStackFrame) {
+    record_exception!(isf);
     panic!("Int 0x1(Debug) occurred!")
 }
 
-extern "x86-interrupt" fn nmi(_isf: InterruptStackFrame) {
+extern "x86-interrupt" fn nmi(isf: InterruptStackFrame) {
+    record_exception!(isf);
     panic!("Int 0x2(NMI) occurred!")
 }
 
-extern "x86-interrupt" fn breakpoint(_isf: InterruptStackFrame) {
+extern "x86-interrupt" fn breakpoint(isf: InterruptStackFrame) {
+    record_exception!(isf);
     panic!("Int 0x3(Breakpoint) occurred!")
 }
 
-extern "x86-interrupt" fn overflow(_isf: InterruptStackFrame) {
+extern "x86-interrupt" fn overflow(isf: InterruptStackFrame) {
+    record_exception!(isf);
     panic!("Overflow occurred!")
 }
 
-extern "x86-interrupt" fn bound(_isf: InterruptStackFrame) {
+extern "x86-interrupt" fn bound(isf: InterruptStackFrame) {
+    record_exception!(isf);
     panic!("Int 0x5(Bound) occurred!")
 }
 
-extern "x86-interrupt" fn opcode(_isf: InterruptStackFrame) {
+extern "x86-interrupt" fn opcode(isf: InterruptStackFrame) {
+    record_exception!(isf);
     panic!("Int 0x6(Opcode) occurred!")
 }
 
-extern "x86-interrupt" fn device(_isf: InterruptStackFrame) {
+extern "x86-interrupt" fn device(isf: InterruptStackFrame) {
+    record_exception!(isf);
     panic!("Int 0x7(Device) occurred!")
 }
 
 extern "x86-interrupt" fn double(isf: InterruptStackFrame, error_code: u64) -> ! {
+    record_exception!(isf);
     panic!("Exception: Double Fault\nCode = {}\n{:#?}", error_code, isf);
 }
 
-extern "x86-interrupt" fn tss(_isf: InterruptStackFrame, _code: u64) {
+extern "x86-interrupt" fn tss(isf: InterruptStackFrame, _code: u64) {
+    record_exception!(isf);
     panic!("Int 0xA(TSS) occurred!")
 }
 
-extern "x86-interrupt" fn segment(_isf: InterruptStackFrame, _code: u64) {
+extern "x86-interrupt" fn segment(isf: InterruptStackFrame, _code: u64) {
+    record_exception!(isf);
     panic!("Int 0xB(Segment) occurred!")
 }
 
-extern "x86-interrupt" fn stack(_isf: InterruptStackFrame, _code: u64) {
+extern "x86-interrupt" fn stack(isf: InterruptStackFrame, _code: u64) {
+    record_exception!(isf);
     panic!("Int 0xC(Stack) occurred!")
 }
 
-extern "x86-interrupt" fn protection(_isf: InterruptStackFrame, _code: u64) {
+extern "x86-interrupt" fn protection(isf: InterruptStackFrame, _code: u64) {
+    record_exception!(isf);
     panic!("Int 0xD(Protection) occurred!")
 }
 
 extern "x86-interrupt" fn page(isf: InterruptStackFrame, code: PageFaultErrorCode) {
+    record_exception!(isf);
     panic!("Exception: Page Fault\nAccessed address: {:?}\nError code: {:?}\n{:#?}", Cr2::read(), code, isf);
 }
 
-extern "x86-interrupt" fn x87(_isf: InterruptStackFrame) {
+extern "x86-interrupt" fn x87(isf: InterruptStackFrame) {
+    record_exception!(isf);
     panic!("Int 0x10(x87) occurred!")
 }
 
-extern "x86-interrupt" fn alignment(_isf: InterruptStackFrame, _code: u64) {
+extern "x86-interrupt" fn alignment(isf: InterruptStackFrame, _code: u64) {
+    record_exception!(isf);
     panic!("Int 0x11(Alignment) occurred!")
 }
 
 extern "x86-interrupt" fn machine(isf: InterruptStackFrame) -> ! {
+    record_exception!(isf);
     panic!("Interrupt 0x12(Machine check, #MC) occurred.\n{:#?}.\nAborting.", isf);
 }
 
-extern "x86-interrupt" fn simd(_isf: InterruptStackFrame) {
+extern "x86-interrupt" fn simd(isf: InterruptStackFrame) {
+    record_exception!(isf);
     panic!("Int 0x13(SIMD) occurred!")
 }
 
-extern "x86-interrupt" fn virtualization(_isf: InterruptStackFrame) {
+extern "x86-interrupt" fn virtualization(isf: InterruptStackFrame) {
+    record_exception!(isf);
     panic!("Int 0x14 occurred!")
 }
 
-extern "x86-interrupt" fn security(_isf: InterruptStackFrame, _code: u64) {
+extern "x86-interrupt" fn security(isf: InterruptStackFrame, _code: u64) {
+    record_exception!(isf);
     panic!("Int 0x1E(Security) occurred!")
 }
 
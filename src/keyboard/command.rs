@@ -0,0 +1,333 @@
+/****************************************************************/
+//                            Uses                              //
+/****************************************************************/
+
+use spin::Mutex;
+use x86_64::instructions::{
+    hlt,
+    interrupts
+};
+use crate::ps2;
+
+/****************************************************************/
+//                         Constants                            //
+/****************************************************************/
+
+pub const ACK: u8 = 0xFA;
+pub const RESEND: u8 = 0xFE;
+pub const ECHO: u8 = 0xEE;
+
+pub const LED_SCROLL_LOCK: u8 = 0x01;
+pub const LED_NUM_LOCK: u8 = 0x02;
+pub const LED_CAPS_LOCK: u8 = 0x04;
+
+/* Commands waiting behind the one being executed */
+const QUEUE_LENGTH: usize = 8;
+
+const MAX_RESENDS: u8 = 3;
+
+/* Timer ticks before a command is given up; 2 seconds at 18.2 Hz */
+#[cfg(not(feature = "time"))]
+const TIMEOUT: usize = 36;
+
+/****************************************************************/
+//                            Types                             //
+/****************************************************************/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    SetLeds(u8),     //< `LED_*` bits
+    Echo,
+    Identify,
+    ScancodeSet(u8), //< 1..3 selects the set, 0 asks for the current one
+    Typematic(u8),   //< See `Delay` and `typematic`
+    Enable,
+    Disable,
+    Reset
+}
+
+impl Command {
+    /* Bytes to send and how many bytes the keyboard answers with after ACK */
+    fn encode(self) -> ([u8; 2], usize, usize) {
+        match self {
+            Command::SetLeds(leds) => ([0xED, leds & 0x07], 2, 0),
+            Command::Echo => ([0xEE, 0], 1, 0),
+            Command::Identify => ([0xF2, 0], 1, 2),
+            Command::ScancodeSet(0) => ([0xF0, 0], 2, 1),
+            Command::ScancodeSet(set) => ([0xF0, set], 2, 0),
+            Command::Typematic(byte) => ([0xF3, byte & 0x7F], 2, 0),
+            Command::Enable => ([0xF4, 0], 1, 0),
+            Command::Disable => ([0xF5, 0], 1, 0),
+            Command::Reset => ([0xFF, 0], 1, 1)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum CommandError {
+    QueueFull,
+    Resend,     //< The keyboard asked to resend too many times
+    Timeout,
+    Overwritten //< The result was lost because too many commands completed after it
+}
+
+/* Bytes the keyboard sent after acknowledging a command */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Response {
+    bytes: [u8; 2],
+    len: u8
+}
+
+impl Response {
+    const fn new() -> Self {
+        Response {
+            bytes: [0; 2],
+            len: 0
+        }
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.bytes[..self.len as usize]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Delay {
+    Ms250,
+    Ms500,
+    Ms750,
+    Ms1000
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Request {
+    seq: u32,
+    command: Command
+}
+
+/* Command being executed */
+#[derive(Debug, Clone, Copy)]
+struct Current {
+    request: Request,
+    bytes: [u8; 2],
+    len: usize,
+    sent: usize,     //< Index of the byte waiting for ACK
+    expected: usize, //< Response bytes still to come
+    response: Response,
+    resends: u8,
+    ticks_left: usize //< Timer ticks before the command fails with `Timeout`
+}
+
+struct Commands {
+    queue: [Option <Request>; QUEUE_LENGTH],
+    head: usize,
+    current: Option <Current>,
+    results: [Option <(u32, Result <Response, CommandError>)>; QUEUE_LENGTH],
+    seq: u32
+}
+
+impl Commands {
+    const fn new() -> Self {
+        Commands {
+            queue: [None; QUEUE_LENGTH],
+            head: 0,
+            current: None,
+            results: [None; QUEUE_LENGTH],
+            seq: 0
+        }
+    }
+
+    fn push(&mut self, command: Command) -> Result <u32, CommandError> {
+        let free = (0..QUEUE_LENGTH).map(|i| (self.head + i) % QUEUE_LENGTH).find(|&i| self.queue[i].is_none()).ok_or(CommandError::QueueFull)?;
+        self.seq = self.seq.wrapping_add(1);
+        self.queue[free] = Some(Request { seq: self.seq, command });
+        if self.current.is_none() { self.start_next() }
+        Ok(self.seq)
+    }
+
+    fn start_next(&mut self) {
+        self.current = None;
+        for i in 0..QUEUE_LENGTH {
+            let index = (self.head + i) % QUEUE_LENGTH;
+            if let Some(request) = self.queue[index].take() {
+                self.head = (index + 1) % QUEUE_LENGTH;
+                let (bytes, len, expected) = request.command.encode();
+                self.current = Some(Current { request, bytes, len, sent: 0, expected, response: Response::new(), resends: 0, ticks_left: timeout() });
+                unsafe { write(bytes[0]) }
+                return
+            }
+        }
+    }
+
+    fn finish(&mut self, result: Result <Response, CommandError>) {
+        if let Some(current) = self.current {
+            let seq = current.request.seq;
+            self.results[seq as usize % QUEUE_LENGTH] = Some((seq, result));
+        }
+        self.start_next();
+    }
+
+    /* Returns whether `byte` was an answer to the current command */
+    fn receive(&mut self, byte: u8) -> bool {
+        let mut current = match self.current {
+            Some(current) => current,
+            None => return false
+        };
+        if current.request.command == Command::Echo && byte == ECHO {
+            self.finish(Ok(Response::new()));
+            return true
+        }
+        if current.sent < current.len {
+            match byte {
+                ACK => {
+                    current.sent += 1;
+                    if current.sent < current.len { unsafe { write(current.bytes[current.sent]) } }
+                },
+                RESEND => {
+                    if current.resends == MAX_RESENDS {
+                        self.finish(Err(CommandError::Resend));
+                        return true
+                    }
+                    current.resends += 1;
+                    unsafe { write(current.bytes[current.sent]) }
+                },
+                _ => return false
+            }
+        } else {
+            current.response.bytes[current.response.len as usize] = byte;
+            current.response.len += 1;
+            current.expected -= 1;
+            /* Old AT keyboards and mice send a single identification byte */
+            if current.request.command == Command::Identify && byte != 0xAB { current.expected = 0 }
+        }
+        if current.sent == current.len && current.expected == 0 {
+            self.current = Some(current);
+            self.finish(Ok(current.response));
+        } else {
+            self.current = Some(current);
+        }
+        true
+    }
+
+    fn take_result(&mut self, seq: u32) -> Option <Result <Response, CommandError>> {
+        let slot = &mut self.results[seq as usize % QUEUE_LENGTH];
+        match *slot {
+            Some((s, result)) if s == seq => {
+                *slot = None;
+                Some(result)
+            },
+            Some((s, _)) if s.wrapping_sub(seq) < u32::MAX / 2 => Some(Err(CommandError::Overwritten)),
+            _ => None
+        }
+    }
+
+    /* Fails the current command once its deadline passes, so a lost ACK does not block the queue */
+    fn tick(&mut self) {
+        let current = match self.current.as_mut() {
+            Some(current) => current,
+            None => return
+        };
+        if current.ticks_left == 0 {
+            self.finish(Err(CommandError::Timeout));
+        } else {
+            current.ticks_left -= 1;
+        }
+    }
+
+    /* Gives up the command `seq` if it is still running or queued */
+    fn abort(&mut self, seq: u32) {
+        if matches!(self.current, Some(current) if current.request.seq == seq) {
+            self.finish(Err(CommandError::Timeout));
+        }
+        for request in self.queue.iter_mut() {
+            if matches!(request, Some(r) if r.seq == seq) { *request = None }
+        }
+        self.take_result(seq);
+    }
+}
+
+/****************************************************************/
+//                           Statics                            //
+/****************************************************************/
+
+static COMMANDS: Mutex <Commands> = Mutex::new(Commands::new());
+
+/****************************************************************/
+//                     Other functions                          //
+/****************************************************************/
+
+/* A byte the controller did not take is never acknowledged, so `execute` times out on its own */
+unsafe fn write(byte: u8) {
+    ps2::write_data(byte).ok();
+}
+
+/* Ticks in about 2 seconds at the programmed timer rate */
+#[cfg(feature = "time")]
+fn timeout() -> usize {
+    crate::time::time_to_ticks(crate::time::Time::milliseconds(2000)) as usize
+}
+
+#[cfg(not(feature = "time"))]
+fn timeout() -> usize {
+    TIMEOUT
+}
+
+/* Typematic byte from the delay before repeating and the rate code, 0 is 30 repeats per second and 0x1F is 2 */
+pub const fn typematic(delay: Delay, rate: u8) -> u8 {
+    ((delay as u8) << 5) | (rate & 0x1F)
+}
+
+/* Called by the keyboard IRQ before decoding; returns whether `byte` was consumed by the command layer */
+pub(super) fn isr(byte: u8) -> bool {
+    COMMANDS.lock().receive(byte)
+}
+
+/* Called from the timer IRQ */
+pub(super) fn timer_isr() {
+    COMMANDS.lock().tick();
+}
+
+/* Queues `command` without waiting, safe to call from interrupt handlers */
+pub fn send(command: Command) -> Result <u32, CommandError> {
+    interrupts::without_interrupts(|| COMMANDS.lock().push(command))
+}
+
+/* Queues `command` and waits for the answer; interrupts must be enabled */
+pub fn execute(command: Command) -> Result <Response, CommandError> {
+    let seq = send(command)?;
+    for _ in 0..timeout() {
+        if let Some(result) = interrupts::without_interrupts(|| COMMANDS.lock().take_result(seq)) { return result }
+        hlt();
+    }
+    interrupts::without_interrupts(|| COMMANDS.lock().abort(seq));
+    Err(CommandError::Timeout)
+}
+
+pub fn set_leds(leds: u8) -> Result <u32, CommandError> {
+    send(Command::SetLeds(leds))
+}
+
+pub fn set_typematic(delay: Delay, rate: u8) -> Result <Response, CommandError> {
+    execute(Command::Typematic(typematic(delay, rate)))
+}
+
+/* Checks that the keyboard is alive */
+pub fn echo() -> bool {
+    execute(Command::Echo).is_ok()
+}
+
+/* `AB 83` for MF2 keyboards */
+pub fn identify() -> Result <Response, CommandError> {
+    execute(Command::Identify)
+}
+
+pub fn scancode_set() -> Result <u8, CommandError> {
+    execute(Command::ScancodeSet(0)).map(|response| response.as_slice().first().copied().unwrap_or(0))
+}
+
+/* With the controller translation on, as `ps2::init` leaves it, set 2 still reaches us as set 1 */
+pub fn set_scancode_set(set: u8) -> Result <Response, CommandError> {
+    execute(Command::ScancodeSet(set))
+}
@@ -0,0 +1,110 @@
+/****************************************************************/
+//                            Uses                              //
+/****************************************************************/
+
+use super::{Key, KeyState, KeyEvent, Scancode};
+
+/****************************************************************/
+//                         Constants                            //
+/****************************************************************/
+
+pub const EXTENDED_PREFIX: Scancode = 0xE0;
+pub const PAUSE_PREFIX: Scancode = 0xE1;
+
+/* Pause sends `E1 1D 45 E1 9D C5` on press and nothing on release */
+const PAUSE_SEQUENCE: [Scancode; 6] = [0xE1, 0x1D, 0x45, 0xE1, 0x9D, 0xC5];
+
+/* Shift the keyboard adds around some extended keys for the sake of old software */
+const FAKE_SHIFTS: [Scancode; 2] = [0x2A, 0x36];
+
+/****************************************************************/
+//                            Types                             //
+/****************************************************************/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Prefix {
+    None,
+    Extended,  //< 0xE0 was read
+    Pause(u8)  //< That many bytes of `PAUSE_SEQUENCE` were read
+}
+
+/* Scancode set 1 decoder, turns byte sequences into key events */
+#[derive(Debug, Clone, Copy)]
+pub struct Decoder {
+    prefix: Prefix
+}
+
+impl Decoder {
+    pub const fn new() -> Self {
+        Decoder {
+            prefix: Prefix::None
+        }
+    }
+
+    /* Returns the event once the whole sequence is read */
+    pub fn feed(&mut self, scancode: Scancode) -> Option <KeyEvent> {
+        match self.prefix {
+            Prefix::None => match scancode {
+                EXTENDED_PREFIX => {
+                    self.prefix = Prefix::Extended;
+                    None
+                },
+                PAUSE_PREFIX => {
+                    self.prefix = Prefix::Pause(1);
+                    None
+                },
+                _ => match Key::from(scancode) {
+                    Key::Error => None,
+                    key => Some(KeyEvent::new(key, KeyState::from(scancode)))
+                }
+            },
+            Prefix::Extended => {
+                self.prefix = Prefix::None;
+                if FAKE_SHIFTS.contains(&(scancode & 0x7F)) { return None }
+                extended(scancode & 0x7F).map(|key| KeyEvent::new(key, KeyState::from(scancode)))
+            },
+            Prefix::Pause(read) => {
+                if scancode != PAUSE_SEQUENCE[read as usize] {
+                    self.prefix = Prefix::None;
+                    return None
+                }
+                if read as usize + 1 == PAUSE_SEQUENCE.len() {
+                    self.prefix = Prefix::None;
+                    Some(KeyEvent::new(Key::Pause, KeyState::Press))
+                } else {
+                    self.prefix = Prefix::Pause(read + 1);
+                    None
+                }
+            }
+        }
+    }
+}
+
+/****************************************************************/
+//                     Other functions                          //
+/****************************************************************/
+
+/* Key of `E0 code` */
+fn extended(code: Scancode) -> Option <Key> {
+    match code {
+        0x1C => Some(Key::NumpadEnter),
+        0x1D => Some(Key::ControlRight),
+        0x35 => Some(Key::NumpadSlash),
+        0x37 => Some(Key::PrintScreen),
+        0x38 => Some(Key::AltRight),
+        0x47 => Some(Key::Home),
+        0x48 => Some(Key::Up),
+        0x49 => Some(Key::PageUp),
+        0x4B => Some(Key::Left),
+        0x4D => Some(Key::Right),
+        0x4F => Some(Key::End),
+        0x50 => Some(Key::Down),
+        0x51 => Some(Key::PageDown),
+        0x52 => Some(Key::Insert),
+        0x53 => Some(Key::Delete),
+        0x5B => Some(Key::MetaLeft),
+        0x5C => Some(Key::MetaRight),
+        0x5D => Some(Key::Menu),
+        _ => None
+    }
+}
@@ -0,0 +1,313 @@
+/****************************************************************/
+//                            Uses                              //
+/****************************************************************/
+
+use alloc::{
+    boxed::Box,
+    format,
+    string::String,
+    sync::Arc,
+    vec::Vec
+};
+use spin::Mutex;
+use crate::{print, tty};
+use super::{keymap, Key, KeyEvent, Priority, Propagation, PRIORITY_LOW};
+
+/****************************************************************/
+//                         Constants                            //
+/****************************************************************/
+
+/* Lines remembered by an editor unless told otherwise */
+pub const DEFAULT_HISTORY: usize = 32;
+
+/* Longest line, in characters */
+pub const MAX_LINE: usize = 256;
+
+/* Just above the line discipline, so it does not see the keys while a line is being edited */
+const PRIORITY: Priority = PRIORITY_LOW + 1;
+
+const ERASE_BELOW: &str = "\x1b[J";
+
+/****************************************************************/
+//                            Types                             //
+/****************************************************************/
+
+/* Source of tab completions */
+pub trait Completer: Send {
+    /* Candidates for `word`, the text between the last space before the cursor and the cursor */
+    fn complete(&mut self, word: &str) -> Vec <String>;
+}
+
+impl <F: FnMut(&str) -> Vec <String> + Send> Completer for F {
+    fn complete(&mut self, word: &str) -> Vec <String> {
+        self(word)
+    }
+}
+
+/* Line being edited */
+struct Line {
+    chars: Vec <char>,
+    cursor: usize,
+    browsing: Option <usize>,   //< History entry shown, counted from the newest
+    draft: Vec <char>,          //< What was typed before browsing the history
+    tabbed: bool,               //< Whether the previous key was an ambiguous Tab
+    start: usize,               //< Column where the line starts, right after the prompt
+    width: usize,               //< Columns of the screen
+    shown: usize                //< Where the cursor is on the screen, in characters from the start
+}
+
+impl Line {
+    fn new() -> Self {
+        Line {
+            chars: Vec::new(),
+            cursor: 0,
+            browsing: None,
+            draft: Vec::new(),
+            tabbed: false,
+            start: 0,
+            width: 1,
+            shown: 0
+        }
+    }
+
+    /* Takes the cursor as the start of the line; the prompt must be printed already */
+    fn begin(&mut self) {
+        self.start = tty::cursor_position().0 as usize;
+        self.width = tty::width() as usize;
+        self.shown = 0;
+    }
+
+    fn insert(&mut self, c: char) {
+        if self.chars.len() == MAX_LINE { return }
+        self.chars.insert(self.cursor, c);
+        self.cursor += 1;
+    }
+
+    fn set(&mut self, chars: &[char]) {
+        self.chars = chars.to_vec();
+        self.cursor = self.chars.len();
+    }
+
+    /* Start of the word under the cursor */
+    fn word_start(&self) -> usize {
+        self.chars[..self.cursor].iter().rposition(|&c| c == ' ').map_or(0, |i| i + 1)
+    }
+
+    /* Sequences moving the cursor back from `from` characters after the start of the line to `to`.
+       The moves are relative, so they stay right when the screen scrolls under the line */
+    fn back(&self, from: usize, to: usize) -> String {
+        let (from, to) = (self.start + from, self.start + to);
+        let mut moves = String::new();
+        let up = from / self.width - to / self.width;
+        if up > 0 { moves += &format!("\x1b[{}A", up) }
+        moves += &format!("\x1b[{}D", self.width);
+        if to % self.width > 0 { moves += &format!("\x1b[{}C", to % self.width) }
+        moves
+    }
+
+    /* Draws the whole line again and leaves the cursor at `cursor` */
+    fn redraw(&mut self) {
+        let text: String = self.chars.iter().collect();
+        print!("{}{}{}{}", self.back(self.shown, 0), text, ERASE_BELOW, self.back(self.chars.len(), self.cursor));
+        self.shown = self.cursor;
+    }
+}
+
+/* Interactive line input with cursor movement, history and tab completion */
+pub struct LineEditor {
+    history: Vec <String>, //< The oldest first
+    capacity: usize,
+    completer: Option <Box <dyn Completer>>
+}
+
+impl LineEditor {
+    pub const fn new() -> Self {
+        Self::with_history(DEFAULT_HISTORY)
+    }
+
+    pub const fn with_history(capacity: usize) -> Self {
+        LineEditor {
+            history: Vec::new(),
+            capacity,
+            completer: None
+        }
+    }
+
+    pub fn set_completer(&mut self, completer: impl Completer + 'static) {
+        self.completer = Some(Box::new(completer));
+    }
+
+    pub fn remove_completer(&mut self) {
+        self.completer = None;
+    }
+
+    /* Remembers `line` unless it is empty or repeats the newest entry */
+    pub fn add_history(&mut self, line: &str) {
+        if line.is_empty() || self.history.last().map_or(false, |last| last == line) || self.capacity == 0 { return }
+        if self.history.len() == self.capacity { self.history.remove(0); }
+        self.history.push(String::from(line));
+    }
+
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    /* Prints `prompt` and lets the user edit a line; it is stored in `s` with the trailing '\n'.
+       Keys are taken from the keyboard queue, so the handlers above the line discipline still run.
+       Ctrl+C abandons the line: `s` is left empty and the signal stays raised for the caller */
+    pub fn read_line(&mut self, prompt: &str, s: &mut String) {
+        /* A signal raised before the prompt belongs to whatever ran before */
+        super::take_interrupt();
+        s.clear();
+        let captured: Arc <Mutex <Vec <KeyEvent>>> = Arc::new(Mutex::new(Vec::new()));
+        let sink = captured.clone();
+        let _grab = super::register_handler(PRIORITY, move |event: KeyEvent| {
+            sink.lock().push(event);
+            Propagation::Stop
+        });
+
+        print!("{}", prompt);
+        let mut line = Line::new();
+        line.begin();
+        loop {
+            super::next_event();
+            let events = core::mem::take(&mut *captured.lock());
+            if super::interrupted() {
+                print!("^C\n");
+                return
+            }
+            for event in events {
+                if self.key(prompt, &mut line, event) {
+                    /* The newline must go after the whole line, which may wrap below the cursor */
+                    line.cursor = line.chars.len();
+                    line.redraw();
+                    s.extend(line.chars.iter());
+                    self.add_history(s);
+                    s.push('\n');
+                    print!("\n");
+                    return
+                }
+            }
+        }
+    }
+
+    /* Applies a key to `line`, returns whether the line is finished */
+    fn key(&mut self, prompt: &str, line: &mut Line, event: KeyEvent) -> bool {
+        if event.is_release() { return false }
+        let tabbed = core::mem::replace(&mut line.tabbed, false);
+        if event.modifiers.ctrl() {
+            match event.key {
+                Key::A => line.cursor = 0,
+                Key::E => line.cursor = line.chars.len(),
+                Key::K => { line.chars.truncate(line.cursor); },
+                Key::U => {
+                    line.chars.drain(..line.cursor);
+                    line.cursor = 0;
+                },
+                Key::W => {
+                    let start = line.chars[..line.cursor].iter().rposition(|&c| c != ' ').map_or(0, |end| line.chars[..end].iter().rposition(|&c| c == ' ').map_or(0, |i| i + 1));
+                    line.chars.drain(start..line.cursor);
+                    line.cursor = start;
+                },
+                _ => return false
+            }
+            line.redraw();
+            return false
+        }
+        match event.key {
+            Key::Enter | Key::NumpadEnter => return true,
+            Key::Left => line.cursor = line.cursor.saturating_sub(1),
+            Key::Right => line.cursor = (line.cursor + 1).min(line.chars.len()),
+            Key::Home => line.cursor = 0,
+            Key::End => line.cursor = line.chars.len(),
+            Key::Backspace => {
+                if line.cursor == 0 { return false }
+                line.cursor -= 1;
+                line.chars.remove(line.cursor);
+            },
+            Key::Delete => {
+                if line.cursor == line.chars.len() { return false }
+                line.chars.remove(line.cursor);
+            },
+            Key::Up => self.browse(line, true),
+            Key::Down => self.browse(line, false),
+            Key::Tab => self.complete(prompt, line, tabbed),
+            _ => match event.as_char().and_then(keymap::compose) {
+                Some(c) if !c.is_control() => line.insert(c),
+                _ => return false
+            }
+        }
+        line.redraw();
+        false
+    }
+
+    /* Up shows older entries, Down newer ones and finally the draft */
+    fn browse(&self, line: &mut Line, older: bool) {
+        let count = self.history.len();
+        let next = match (line.browsing, older) {
+            (None, true) if count > 0 => Some(0),
+            (None, _) => return,
+            (Some(i), true) => Some((i + 1).min(count - 1)),
+            (Some(0), false) => None,
+            (Some(i), false) => Some(i - 1)
+        };
+        if line.browsing.is_none() { line.draft = line.chars.clone() }
+        line.browsing = next;
+        match next {
+            Some(i) => {
+                let entry: Vec <char> = self.history[count - 1 - i].chars().collect();
+                line.set(&entry);
+            },
+            None => {
+                let draft = core::mem::take(&mut line.draft);
+                line.set(&draft);
+            }
+        }
+    }
+
+    /* A single candidate is completed, several are completed up to their common prefix
+       and listed on the second Tab in a row */
+    fn complete(&mut self, prompt: &str, line: &mut Line, tabbed: bool) {
+        let completer = match self.completer.as_mut() {
+            Some(completer) => completer,
+            None => return
+        };
+        let start = line.word_start();
+        let word: String = line.chars[start..line.cursor].iter().collect();
+        let candidates = completer.complete(&word);
+        let first = match candidates.first() {
+            Some(first) => first,
+            None => return
+        };
+        let mut common = first.chars().count();
+        for candidate in candidates.iter().skip(1) {
+            common = common.min(first.chars().zip(candidate.chars()).take_while(|(a, b)| a == b).count());
+        }
+        let typed = word.chars().count();
+        if common > typed {
+            for c in first.chars().skip(typed).take(common - typed) { line.insert(c) }
+        }
+        if candidates.len() == 1 {
+            line.insert(' ');
+        } else if common <= typed {
+            if tabbed {
+                print!("\n");
+                for candidate in candidates.iter() { print!("{}  ", candidate) }
+                print!("\n{}", prompt);
+                line.begin();
+            } else {
+                line.tabbed = true;
+            }
+        }
+    }
+}
+
+impl Default for LineEditor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,27 @@
+/****************************************************************/
+//                            Uses                              //
+/****************************************************************/
+
+use crate::handler::Handlers;
+pub use crate::handler::{Handler, Priority, Propagation, PRIORITY_HIGH, PRIORITY_NORMAL, PRIORITY_LOW};
+use super::KeyEvent;
+
+/****************************************************************/
+//                            Types                             //
+/****************************************************************/
+
+pub type HandlerHandle = crate::handler::HandlerHandle <KeyEvent>;
+
+/****************************************************************/
+//                           Statics                            //
+/****************************************************************/
+
+pub(super) static HANDLERS: Handlers <KeyEvent> = Handlers::new();
+
+/****************************************************************/
+//                     Other functions                          //
+/****************************************************************/
+
+pub fn register_handler(priority: Priority, handler: impl Handler <KeyEvent> + 'static) -> HandlerHandle {
+    HANDLERS.register(priority, handler)
+}
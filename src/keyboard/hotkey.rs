@@ -0,0 +1,161 @@
+/****************************************************************/
+//                            Uses                              //
+/****************************************************************/
+
+use core::sync::atomic::{AtomicBool, Ordering};
+use spin::Mutex;
+use x86_64::{
+    instructions::interrupts,
+    registers::{
+        control::{Cr0, Cr2, Cr3, Cr4},
+        rflags::RFlags
+    },
+    structures::idt::InterruptStackFrame
+};
+use crate::{ps2, try_print, try_println};
+use super::{Key, KeyEvent, Modifiers, Propagation};
+
+/****************************************************************/
+//                         Constants                            //
+/****************************************************************/
+
+pub const MAX_HOTKEYS: usize = 32;
+
+const SYSRQ: Modifiers = Modifiers::ALT.with(Modifiers::SYSRQ);
+
+/****************************************************************/
+//                            Types                             //
+/****************************************************************/
+
+/* Runs in the keyboard IRQ, so it must not block, print with `try_print!`; `Stop` keeps the key away from the handlers.
+   The frame is the one of the code the keyboard IRQ interrupted */
+pub type Action = fn(KeyEvent, &InterruptStackFrame) -> Propagation;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum HotkeyError {
+    Full,
+    Taken //< Another action is bound to the combination
+}
+
+#[derive(Clone, Copy)]
+struct Hotkey {
+    modifiers: Modifiers,
+    key: Key,
+    action: Action
+}
+
+/****************************************************************/
+//                           Statics                            //
+/****************************************************************/
+
+static HOTKEYS: Mutex <[Option <Hotkey>; MAX_HOTKEYS]> = Mutex::new([None; MAX_HOTKEYS]);
+
+/* Raised by Ctrl+C */
+static INTERRUPT: AtomicBool = AtomicBool::new(false);
+
+/****************************************************************/
+//                     Other functions                          //
+/****************************************************************/
+
+/* Binds `action` to pressing `key` while exactly the held `modifiers` are down; locks do not matter */
+pub fn register_hotkey(modifiers: Modifiers, key: Key, action: Action) -> Result <(), HotkeyError> {
+    let modifiers = modifiers.held();
+    interrupts::without_interrupts(|| {
+        let mut hotkeys = HOTKEYS.lock();
+        if hotkeys.iter().flatten().any(|h| h.modifiers == modifiers && h.key == key) { return Err(HotkeyError::Taken) }
+        let slot = hotkeys.iter_mut().find(|h| h.is_none()).ok_or(HotkeyError::Full)?;
+        *slot = Some(Hotkey { modifiers, key, action });
+        Ok(())
+    })
+}
+
+/* Returns whether something was bound */
+pub fn unregister_hotkey(modifiers: Modifiers, key: Key) -> bool {
+    let modifiers = modifiers.held();
+    interrupts::without_interrupts(|| {
+        for hotkey in HOTKEYS.lock().iter_mut() {
+            if matches!(hotkey, Some(h) if h.modifiers == modifiers && h.key == key) {
+                *hotkey = None;
+                return true
+            }
+        }
+        false
+    })
+}
+
+/* Called by the keyboard IRQ before the event is queued */
+pub(super) fn isr(event: KeyEvent, frame: &InterruptStackFrame) -> Propagation {
+    if event.is_release() || event.repeat { return Propagation::Continue }
+    let modifiers = event.modifiers.held();
+    let action = HOTKEYS.lock().iter().flatten().find(|h| h.modifiers == modifiers && h.key == event.key).map(|h| h.action);
+    match action {
+        Some(action) => action(event, frame),
+        None => Propagation::Continue
+    }
+}
+
+/* Binds the built-in hotkeys */
+pub(super) fn init() {
+    register_hotkey(Modifiers::CTRL | Modifiers::ALT, Key::Delete, reboot).ok();
+    register_hotkey(Modifiers::CTRL, Key::C, interrupt).ok();
+    register_hotkey(SYSRQ, Key::H, sysrq_help).ok();
+    register_hotkey(SYSRQ, Key::P, sysrq_registers).ok();
+    register_hotkey(SYSRQ, Key::M, sysrq_memory).ok();
+    register_hotkey(SYSRQ, Key::C, sysrq_panic).ok();
+    register_hotkey(SYSRQ, Key::B, reboot).ok();
+}
+
+/* Whether Ctrl+C was pressed since the signal was last taken */
+pub fn interrupted() -> bool {
+    INTERRUPT.load(Ordering::Relaxed)
+}
+
+/* Clears the signal, returning whether it was raised */
+pub fn take_interrupt() -> bool {
+    INTERRUPT.swap(false, Ordering::Relaxed)
+}
+
+pub fn raise_interrupt() {
+    INTERRUPT.store(true, Ordering::Relaxed)
+}
+
+fn reboot(_: KeyEvent, _: &InterruptStackFrame) -> Propagation {
+    ps2::reboot()
+}
+
+/* The key still reaches the reader, which wakes it up to notice the signal */
+fn interrupt(_: KeyEvent, _: &InterruptStackFrame) -> Propagation {
+    raise_interrupt();
+    Propagation::Continue
+}
+
+fn sysrq_help(_: KeyEvent, _: &InterruptStackFrame) -> Propagation {
+    try_println!("SysRq: b - reboot, c - panic, h - help, m - memory, p - registers");
+    Propagation::Stop
+}
+
+/* Registers of the interrupted code; the backtrace walks through the IRQ into it */
+fn sysrq_registers(_: KeyEvent, frame: &InterruptStackFrame) -> Propagation {
+    /* One call, so a busy console drops the whole dump rather than some of its lines */
+    try_print!(
+        "SysRq: registers\nrip {:#018x} rsp {:#018x}\ncs {:#06x} ss {:#06x} rflags {:?}\ncr0 {:?}\ncr2 {:?}\ncr3 {:?}\ncr4 {:?}\n",
+        frame.instruction_pointer.as_u64(), frame.stack_pointer.as_u64(),
+        frame.code_segment, frame.stack_segment, RFlags::from_bits_truncate(frame.cpu_flags),
+        Cr0::read(), Cr2::read(), Cr3::read(), Cr4::read()
+    );
+    #[cfg(feature = "backtrace")]
+    try_print!("{}", crate::backtrace::Backtrace::capture());
+    Propagation::Stop
+}
+
+fn sysrq_memory(_: KeyEvent, _: &InterruptStackFrame) -> Propagation {
+    let used = crate::allocator::used();
+    let size = crate::allocator::HEAP_SIZE;
+    try_println!("SysRq: heap {}K used, {}K free of {}K", used / 1024, (size - used.min(size)) / 1024, size / 1024);
+    Propagation::Stop
+}
+
+fn sysrq_panic(_: KeyEvent, _: &InterruptStackFrame) -> Propagation {
+    panic!("SysRq: forced panic")
+}
@@ -0,0 +1,135 @@
+/****************************************************************/
+//                            Uses                              //
+/****************************************************************/
+
+use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use x86_64::structures::idt::InterruptStackFrame;
+use super::{Key, KeyEvent, Propagation};
+
+/****************************************************************/
+//                         Constants                            //
+/****************************************************************/
+
+/* Dead keys put combining accents into the tables */
+pub const DEAD_GRAVE: char = '\u{300}';
+pub const DEAD_ACUTE: char = '\u{301}';
+pub const DEAD_CIRCUMFLEX: char = '\u{302}';
+
+/* Keys whose characters depend on the layout, in the order of the layout strings */
+const KEYS: [Key; 47] = [
+    Key::Num1, Key::Num2, Key::Num3, Key::Num4, Key::Num5, Key::Num6, Key::Num7, Key::Num8, Key::Num9, Key::Num0, Key::Minus, Key::Equal,
+    Key::Q, Key::W, Key::E, Key::R, Key::T, Key::Y, Key::U, Key::I, Key::O, Key::P, Key::SquareLeft, Key::SquareRight,
+    Key::A, Key::S, Key::D, Key::F, Key::G, Key::H, Key::J, Key::K, Key::L, Key::Semicolon, Key::Quote,
+    Key::Apostrophe,
+    Key::Backslash,
+    Key::Z, Key::X, Key::C, Key::V, Key::B, Key::N, Key::M, Key::Comma, Key::Dot, Key::Slash
+];
+
+/* (accent, base, composed); accent followed by space gives the accent itself */
+const COMPOSITIONS: [(char, char, char); 33] = [
+    (DEAD_GRAVE, 'a', 'à'), (DEAD_GRAVE, 'e', 'è'), (DEAD_GRAVE, 'i', 'ì'), (DEAD_GRAVE, 'o', 'ò'), (DEAD_GRAVE, 'u', 'ù'),
+    (DEAD_GRAVE, 'A', 'À'), (DEAD_GRAVE, 'E', 'È'), (DEAD_GRAVE, 'I', 'Ì'), (DEAD_GRAVE, 'O', 'Ò'), (DEAD_GRAVE, 'U', 'Ù'),
+    (DEAD_GRAVE, ' ', '`'),
+    (DEAD_ACUTE, 'a', 'á'), (DEAD_ACUTE, 'e', 'é'), (DEAD_ACUTE, 'i', 'í'), (DEAD_ACUTE, 'o', 'ó'), (DEAD_ACUTE, 'u', 'ú'),
+    (DEAD_ACUTE, 'A', 'Á'), (DEAD_ACUTE, 'E', 'É'), (DEAD_ACUTE, 'I', 'Í'), (DEAD_ACUTE, 'O', 'Ó'), (DEAD_ACUTE, 'U', 'Ú'),
+    (DEAD_ACUTE, ' ', '´'),
+    (DEAD_CIRCUMFLEX, 'a', 'â'), (DEAD_CIRCUMFLEX, 'e', 'ê'), (DEAD_CIRCUMFLEX, 'i', 'î'), (DEAD_CIRCUMFLEX, 'o', 'ô'), (DEAD_CIRCUMFLEX, 'u', 'û'),
+    (DEAD_CIRCUMFLEX, 'A', 'Â'), (DEAD_CIRCUMFLEX, 'E', 'Ê'), (DEAD_CIRCUMFLEX, 'I', 'Î'), (DEAD_CIRCUMFLEX, 'O', 'Ô'), (DEAD_CIRCUMFLEX, 'U', 'Û'),
+    (DEAD_CIRCUMFLEX, ' ', '^')
+];
+
+/****************************************************************/
+//                            Types                             //
+/****************************************************************/
+
+/* Characters of `KEYS` without and with Shift */
+#[derive(Debug)]
+pub struct Layout {
+    pub name: &'static str,
+    low: &'static str,
+    high: &'static str
+}
+
+impl Layout {
+    pub fn translate(&self, key: Key, high: bool) -> Option <char> {
+        let index = KEYS.iter().position(|&k| k == key)?;
+        if high { self.high.chars().nth(index) } else { self.low.chars().nth(index) }
+    }
+}
+
+/****************************************************************/
+//                           Statics                            //
+/****************************************************************/
+
+pub static US: Layout = Layout {
+    name: "us",
+    low:  "1234567890-=qwertyuiop[]asdfghjkl;'`\\zxcvbnm,./",
+    high: "!@#$%^&*()_+QWERTYUIOP{}ASDFGHJKL:\"~|ZXCVBNM<>?"
+};
+
+pub static RUSSIAN: Layout = Layout {
+    name: "ru",
+    low:  "1234567890-=йцукенгшщзхъфывапролджэё\\ячсмитьбю.",
+    high: "!\"№;%:?*()_+ЙЦУКЕНГШЩЗХЪФЫВАПРОЛДЖЭЁ/ЯЧСМИТЬБЮ,"
+};
+
+pub static DVORAK: Layout = Layout {
+    name: "dvorak",
+    low:  "1234567890[]',.pyfgcrl/=aoeuidhtns-`\\;qjkxbmwvz",
+    high: "!@#$%^&*(){}\"<>PYFGCRL?+AOEUIDHTNS_~|:QJKXBMWVZ"
+};
+
+pub static GERMAN: Layout = Layout {
+    name: "de",
+    low:  "1234567890ß\u{301}qwertzuiopü+asdfghjklöä\u{302}#yxcvbnm,.-",
+    high: "!\"§$%&/()=?\u{300}QWERTZUIOPÜ*ASDFGHJKLÖÄ°'YXCVBNM;:_"
+};
+
+pub static LAYOUTS: [&Layout; 4] = [&US, &RUSSIAN, &DVORAK, &GERMAN];
+
+static ACTIVE: AtomicUsize = AtomicUsize::new(0);
+
+/* Accent of the dead key pressed last, 0 if none */
+static DEAD: AtomicU32 = AtomicU32::new(0);
+
+/****************************************************************/
+//                     Other functions                          //
+/****************************************************************/
+
+pub fn layout() -> &'static Layout {
+    LAYOUTS[ACTIVE.load(Ordering::Relaxed)]
+}
+
+/* Switches to `LAYOUTS[index]` */
+pub fn set_layout(index: usize) {
+    if index >= LAYOUTS.len() { return }
+    ACTIVE.store(index, Ordering::Relaxed);
+    DEAD.store(0, Ordering::Relaxed);
+}
+
+pub fn next_layout() {
+    set_layout((ACTIVE.load(Ordering::Relaxed) + 1) % LAYOUTS.len())
+}
+
+pub fn is_dead(c: char) -> bool {
+    matches!(c, DEAD_GRAVE | DEAD_ACUTE | DEAD_CIRCUMFLEX)
+}
+
+/* Applies pending dead key to typed `c`; returns `None` while waiting for the next key.
+   Meant to be called once per typed character, e.g. by the line discipline */
+pub fn compose(c: char) -> Option <char> {
+    if is_dead(c) {
+        DEAD.store(c as u32, Ordering::Relaxed);
+        return None
+    }
+    match char::from_u32(DEAD.swap(0, Ordering::Relaxed)) {
+        Some(accent) if accent != '\0' => Some(COMPOSITIONS.iter().find(|&&(a, base, _)| a == accent && base == c).map_or(c, |&(_, _, composed)| composed)),
+        _ => Some(c)
+    }
+}
+
+/* Hotkey for Alt+Shift, switches to the next layout once per press; the modifier itself still goes on */
+pub fn switch_layout(_: KeyEvent, _: &InterruptStackFrame) -> Propagation {
+    next_layout();
+    Propagation::Continue
+}
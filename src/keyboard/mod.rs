@@ -0,0 +1,622 @@
+/****************************************************************/
+//                          Modules                             //
+/****************************************************************/
+
+#[cfg(all(feature = "keyboard", feature = "allocator"))]
+pub mod command;
+
+#[cfg(all(feature = "keyboard", feature = "allocator"))]
+mod decoder;
+
+#[cfg(all(feature = "keyboard", feature = "allocator"))]
+pub mod editor;
+
+#[cfg(all(feature = "keyboard", feature = "allocator"))]
+mod handler;
+
+#[cfg(all(feature = "keyboard", feature = "allocator"))]
+pub mod hotkey;
+
+#[cfg(all(feature = "keyboard", feature = "allocator"))]
+pub mod keymap;
+
+#[cfg(all(feature = "keyboard", feature = "allocator"))]
+mod queue;
+
+#[cfg(all(feature = "keyboard", feature = "allocator", feature = "time"))]
+mod repeat;
+
+#[cfg(all(feature = "keyboard", feature = "allocator"))]
+mod private {
+    /****************************************************************/
+    //                            Uses                              //
+    /****************************************************************/
+
+    use spin;
+
+    use alloc::string::String;
+
+    use x86_64::{
+        instructions::{
+            interrupts,
+            port::Port
+        },
+        structures::idt::InterruptStackFrame
+    };
+    use core::{
+        fmt::Debug,
+        convert::From,
+        ops::BitOr,
+        sync::atomic::{AtomicU8, Ordering}
+    };
+    use crate::tty;
+    #[cfg(feature = "time")]
+    use crate::time::{self, Time};
+    #[cfg(feature = "time")]
+    use super::repeat;
+    use super::{
+        command,
+        decoder::Decoder,
+        editor::{LineEditor, Completer},
+        handler::{self, Propagation, PRIORITY_LOW},
+        hotkey,
+        keymap
+    };
+
+    /****************************************************************/
+    //                         Constants                            //
+    /****************************************************************/
+
+    const KB_PORT: u16 = 0x60;
+
+    /****************************************************************/
+    //                            Types                             //
+    /****************************************************************/
+
+    pub type Scancode = u8;
+
+    #[allow(dead_code)]
+    #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+    #[repr(u8)]
+    pub enum Key {
+        Error,
+        Escape,
+        Num1,
+        Num2,
+        Num3,
+        Num4,
+        Num5,
+        Num6,
+        Num7,
+        Num8,
+        Num9,
+        Num0,
+        Minus,
+        Equal,
+        Backspace,
+        Tab,
+        Q,
+        W,
+        E,
+        R,
+        T,
+        Y,
+        U,
+        I,
+        O,
+        P,
+        SquareLeft,  //< [
+        SquareRight, //< ]
+        Enter,
+        ControlLeft,
+        A,
+        S,
+        D,
+        F,
+        G,
+        H,
+        J,
+        K,
+        L,
+        Semicolon,
+        Quote,
+        Apostrophe,
+        ShiftLeft,
+        Backslash,
+        Z,
+        X,
+        C,
+        V,
+        B,
+        N,
+        M,
+        Comma,
+        Dot,
+        Slash,
+        ShiftRight,
+        NumpadAsterisk,
+        AltLeft,
+        Space,
+        CapsLock,
+        F1,
+        F2,
+        F3,
+        F4,
+        F5,
+        F6,
+        F7,
+        F8,
+        F9,
+        F10,
+        NumLock,
+        ScrollLock,
+        Numpad7,
+        Numpad8,
+        Numpad9,
+        NumpadMinus,
+        Numpad4,
+        Numpad5,
+        Numpad6,
+        NumpadPlus,
+        Numpad1,
+        Numpad2,
+        Numpad3,
+        Numpad0,
+        NumpadDot,
+
+        SysRq, //< Alt+PrintScreen
+        Skip2,
+        Skip3,
+
+        F11,
+        F12,
+
+        /* Keys below are sent with 0xE0 or 0xE1 prefix and have no single-byte scancode */
+        NumpadEnter,
+        ControlRight,
+        NumpadSlash,
+        PrintScreen,
+        AltRight,
+        Home,
+        Up,
+        PageUp,
+        Left,
+        Right,
+        End,
+        Down,
+        PageDown,
+        Insert,
+        Delete,
+        MetaLeft,  //< Left Windows key
+        MetaRight, //< Right Windows key
+        Menu,
+        Pause,
+
+        Count //< Count of all keys
+    }
+
+    impl Key {
+        pub fn state(self) -> KeyState {
+            match self {
+                Key::Error => KeyState::Release,
+                other => KeyState::from(states()[other as usize / 8] & (1 << (other as usize % 8)) != 0)
+            }
+        }
+
+        pub fn is_pressed(self) -> bool {
+            self.state() == KeyState::Press
+        }
+
+        pub fn is_released(self) -> bool {
+            self.state() == KeyState::Release
+        }
+
+        /* Characters of keys that are the same in every layout */
+        fn as_fixed_char(self) -> Option <char> {
+            match self {
+                Key::NumpadAsterisk => Some('*'),
+                Key::NumpadMinus => Some('-'),
+                Key::NumpadPlus => Some('+'),
+                Key::NumpadSlash => Some('/'),
+                Key::Backspace => Some('\x08'),
+                Key::Tab => Some('\t'),
+                Key::Enter | Key::NumpadEnter => Some('\n'),
+                Key::Space => Some(' '),
+                _ => None
+            }
+        }
+
+        /* Character without Shift in the active layout, dead keys give combining accents */
+        pub fn as_low_char(self) -> Option <char> {
+            self.as_fixed_char().or_else(|| keymap::layout().translate(self, false))
+        }
+
+        /* Character with Shift in the active layout, dead keys give combining accents */
+        pub fn as_high_char(self) -> Option <char> {
+            self.as_fixed_char().or_else(|| keymap::layout().translate(self, true))
+        }
+
+        /* Character with the current modifiers */
+        pub fn as_char(self) -> Option <char> {
+            self.as_char_with(modifiers())
+        }
+
+        /* Caps Lock affects letters only */
+        pub fn as_char_with(self, modifiers: Modifiers) -> Option <char> {
+            let letter = self.as_low_char().map_or(false, char::is_alphabetic);
+            if modifiers.shift() != (modifiers.caps_lock() && letter) { self.as_high_char() } else { self.as_low_char() }
+        }
+    }
+
+    /* Key of a single-byte scancode, prefixed keys are decoded by `Decoder` */
+    impl From <Scancode> for Key {
+        fn from(mut x: Scancode) -> Self {
+            x &= 0x7F;
+            if x > Key::F12 as Scancode { return Key::Error }
+            unsafe { *(&x as *const Scancode as *const Self) }
+        }
+    }
+
+    #[allow(dead_code)]
+    #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+    #[repr(u8)]
+    pub enum KeyState {
+        Release,
+        Press
+    }
+
+    impl KeyState {
+        pub fn is_pressed(self) -> bool {
+            match self {
+                Self::Release => false,
+                Self::Press => true
+            }
+        }
+
+        pub fn is_released(self) -> bool {
+            match self {
+                Self::Release => true,
+                Self::Press => false
+            }
+        }
+    }
+
+    impl From <Scancode> for KeyState {
+        fn from(x: Scancode) -> Self {
+            if x & 0x80 == 0 { Self::Press } else { Self::Release }
+        }
+    }
+
+    impl From <bool> for KeyState {
+        fn from(x: bool) -> Self {
+            if x { Self::Press } else { Self::Release }
+        }
+    }
+
+    /* Modifier keys held and locks on at the moment of a key event */
+    #[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+    pub struct Modifiers(u8);
+
+    impl Modifiers {
+        pub const NONE: Modifiers = Modifiers(0);
+        pub const SHIFT: Modifiers = Modifiers(0x01);
+        pub const CTRL: Modifiers = Modifiers(0x02);
+        pub const ALT: Modifiers = Modifiers(0x04);
+        pub const META: Modifiers = Modifiers(0x08);
+        pub const SYSRQ: Modifiers = Modifiers(0x10);
+        pub const CAPS_LOCK: Modifiers = Modifiers(0x20);
+        pub const NUM_LOCK: Modifiers = Modifiers(0x40);
+
+        /* Modifiers that are held rather than toggled */
+        pub const HELD: Modifiers = Modifiers(0x1F);
+
+        pub const fn bits(self) -> u8 {
+            self.0
+        }
+
+        pub const fn with(self, other: Modifiers) -> Modifiers {
+            Modifiers(self.0 | other.0)
+        }
+
+        pub const fn contains(self, other: Modifiers) -> bool {
+            self.0 & other.0 == other.0
+        }
+
+        /* Only the held modifiers, for matching key combinations */
+        pub const fn held(self) -> Modifiers {
+            Modifiers(self.0 & Self::HELD.0)
+        }
+
+        pub const fn shift(self) -> bool {
+            self.contains(Self::SHIFT)
+        }
+
+        pub const fn ctrl(self) -> bool {
+            self.contains(Self::CTRL)
+        }
+
+        pub const fn alt(self) -> bool {
+            self.contains(Self::ALT)
+        }
+
+        pub const fn meta(self) -> bool {
+            self.contains(Self::META)
+        }
+
+        pub const fn sysrq(self) -> bool {
+            self.contains(Self::SYSRQ)
+        }
+
+        pub const fn caps_lock(self) -> bool {
+            self.contains(Self::CAPS_LOCK)
+        }
+
+        pub const fn num_lock(self) -> bool {
+            self.contains(Self::NUM_LOCK)
+        }
+    }
+
+    impl BitOr for Modifiers {
+        type Output = Modifiers;
+
+        fn bitor(self, other: Modifiers) -> Modifiers {
+            self.with(other)
+        }
+    }
+
+    /* Decoded press or release of a key */
+    #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+    pub struct KeyEvent {
+        pub key: Key,
+        pub state: KeyState,
+        pub modifiers: Modifiers, //< Filled in by the IRQ after the key itself is accounted
+        pub repeat: bool,         //< A press generated while the key is held, by the keyboard or by `set_repeat`
+        #[cfg(feature = "time")]
+        pub time: Time            //< When the IRQ received the key
+    }
+
+    impl KeyEvent {
+        pub const fn new(key: Key, state: KeyState) -> Self {
+            KeyEvent {
+                key,
+                state,
+                modifiers: Modifiers::NONE,
+                repeat: false,
+                #[cfg(feature = "time")]
+                time: time::ZERO
+            }
+        }
+
+        pub fn is_repeat(&self) -> bool {
+            self.repeat
+        }
+
+        /* Character of the key with the modifiers of the event */
+        pub fn as_char(&self) -> Option <char> {
+            self.key.as_char_with(self.modifiers)
+        }
+
+        pub fn is_press(&self) -> bool {
+            self.state.is_pressed()
+        }
+
+        pub fn is_release(&self) -> bool {
+            self.state.is_released()
+        }
+    }
+
+    /****************************************************************/
+    //                           Statics                            //
+    /****************************************************************/
+
+    const STATE_BYTES: usize = (Key::Count as usize + 7) / 8;
+
+    static mut STATES: spin::Mutex <[u8; STATE_BYTES]> = spin::Mutex::new([0; STATE_BYTES]);
+
+    static DECODER: spin::Mutex <Decoder> = spin::Mutex::new(Decoder::new());
+
+    /* Caps/Num/Scroll Lock states as `command::LED_*` bits */
+    static LOCKS: AtomicU8 = AtomicU8::new(0);
+
+    /* Editor behind `readline`, its history is shared by all the prompts */
+    static EDITOR: spin::Mutex <LineEditor> = spin::Mutex::new(LineEditor::new());
+
+    /****************************************************************/
+    //                     Other functions                          //
+    /****************************************************************/
+
+    pub fn keyboard_isr(frame: &InterruptStackFrame) {
+        let mut port = Port::new(KB_PORT);
+        let scancode: Scancode = unsafe { port.read() };
+        if command::isr(scancode) { return }
+        let mut event = match DECODER.lock().feed(scancode) {
+            Some(event) => event,
+            None => return
+        };
+        let KeyEvent { key, state, .. } = event;
+        /* The keyboard repeats make codes while a key is held, only the first one toggles */
+        let first = state.is_pressed() && key.is_released();
+        if first {
+            let led = match key {
+                Key::CapsLock => command::LED_CAPS_LOCK,
+                Key::NumLock => command::LED_NUM_LOCK,
+                Key::ScrollLock => command::LED_SCROLL_LOCK,
+                _ => 0
+            };
+            if led != 0 {
+                let leds = LOCKS.fetch_xor(led, Ordering::Relaxed) ^ led;
+                command::set_leds(leds).ok();
+            }
+        }
+        let byte = key as usize / 8;
+        let bit  = key as usize % 8;
+        /* Pause has no release, so it never stays pressed */
+        if key != Key::Pause {
+            unsafe {
+                match state {
+                    KeyState::Release => STATES.lock()[byte] &= !(1 << bit),
+                    KeyState::Press => STATES.lock()[byte] |= 1 << bit
+                }
+            }
+        }
+        event.modifiers = modifiers();
+        event.repeat = state.is_pressed() && !first;
+        #[cfg(feature = "time")] {
+            event.time = time::now();
+            if repeat::isr(event, first) { return }
+        }
+        /* Hotkeys run right here, so they work even when nobody reads the keyboard */
+        if hotkey::isr(event, frame) == Propagation::Stop { return }
+        queue::push(event);
+    }
+
+    /* Binds built-in hotkeys, which run in the IRQ, and registers built-in handlers; they stay for the whole uptime */
+    pub fn init() {
+        queue::init();
+        hotkey::init();
+        for &key in [Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6].iter() {
+            hotkey::register_hotkey(Modifiers::ALT, key, switch_console).ok();
+        }
+        hotkey::register_hotkey(Modifiers::SHIFT, Key::PageUp, scroll_view).ok();
+        hotkey::register_hotkey(Modifiers::SHIFT, Key::PageDown, scroll_view).ok();
+        for &key in [Key::ShiftLeft, Key::ShiftRight, Key::AltLeft, Key::AltRight].iter() {
+            hotkey::register_hotkey(Modifiers::ALT | Modifiers::SHIFT, key, keymap::switch_layout).ok();
+        }
+        handler::register_handler(PRIORITY_LOW, tty::ldisc::keyboard_handler).forget();
+    }
+
+    /* Alt+F1..F6 switch virtual consoles; the console hotkeys may take the TTY lock in the IRQ,
+       since it is only ever held with interrupts off */
+    fn switch_console(event: KeyEvent, _: &InterruptStackFrame) -> Propagation {
+        let console = match event.key {
+            Key::F1 => 0,
+            Key::F2 => 1,
+            Key::F3 => 2,
+            Key::F4 => 3,
+            Key::F5 => 4,
+            Key::F6 => 5,
+            _ => return Propagation::Continue
+        };
+        tty::switch_console(console);
+        Propagation::Stop
+    }
+
+    /* Shift+PageUp/PageDown scroll the console by half a screen */
+    fn scroll_view(event: KeyEvent, _: &InterruptStackFrame) -> Propagation {
+        match event.key {
+            Key::PageUp => tty::scroll_view_up(tty::height() as usize / 2),
+            Key::PageDown => tty::scroll_view_down(tty::height() as usize / 2),
+            _ => return Propagation::Continue
+        }
+        Propagation::Stop
+    }
+
+    /* Called from the timer IRQ: expires keyboard commands and repeats held keys */
+    pub fn timer_isr() {
+        command::timer_isr();
+        #[cfg(feature = "time")]
+        repeat::timer_isr();
+    }
+
+    /* Copy of the key states; the IRQ updates them, so they are read with interrupts off */
+    fn states() -> [u8; STATE_BYTES] {
+        interrupts::without_interrupts(|| unsafe { *STATES.lock() })
+    }
+
+    pub fn caps() -> bool {
+        LOCKS.load(Ordering::Relaxed) & command::LED_CAPS_LOCK != 0
+    }
+
+    pub fn num_lock() -> bool {
+        LOCKS.load(Ordering::Relaxed) & command::LED_NUM_LOCK != 0
+    }
+
+    pub fn scroll_lock() -> bool {
+        LOCKS.load(Ordering::Relaxed) & command::LED_SCROLL_LOCK != 0
+    }
+
+    /* Changes lock states and the keyboard LEDs at once */
+    pub fn set_locks(leds: u8) {
+        LOCKS.store(leds & 0x07, Ordering::Relaxed);
+        command::set_leds(leds).ok();
+    }
+
+    /* Modifiers held and locks on right now; key events carry their own copy */
+    pub fn modifiers() -> Modifiers {
+        let states = states();
+        let held = |key: Key| states[key as usize / 8] & (1 << (key as usize % 8)) != 0;
+        let locks = LOCKS.load(Ordering::Relaxed);
+        let mut modifiers = Modifiers::NONE;
+        if held(Key::ShiftLeft) || held(Key::ShiftRight) { modifiers = modifiers | Modifiers::SHIFT }
+        if held(Key::ControlLeft) || held(Key::ControlRight) { modifiers = modifiers | Modifiers::CTRL }
+        if held(Key::AltLeft) || held(Key::AltRight) { modifiers = modifiers | Modifiers::ALT }
+        if held(Key::MetaLeft) || held(Key::MetaRight) { modifiers = modifiers | Modifiers::META }
+        /* Some keyboards send PrintScreen with Alt instead of SysRq */
+        if held(Key::SysRq) || held(Key::PrintScreen) { modifiers = modifiers | Modifiers::SYSRQ }
+        if locks & command::LED_CAPS_LOCK != 0 { modifiers = modifiers | Modifiers::CAPS_LOCK }
+        if locks & command::LED_NUM_LOCK != 0 { modifiers = modifiers | Modifiers::NUM_LOCK }
+        modifiers
+    }
+
+    pub fn ctrl() -> bool {
+        Key::ControlLeft.is_pressed() || Key::ControlRight.is_pressed()
+    }
+
+    pub fn alt() -> bool {
+        Key::AltLeft.is_pressed() || Key::AltRight.is_pressed()
+    }
+
+    pub fn shift() -> bool {
+        Key::ShiftLeft.is_pressed() || Key::ShiftRight.is_pressed()
+    }
+
+    pub fn char_up() -> bool {
+        caps() != shift()
+    }
+
+    /* Reads from the line discipline, see `tty::ldisc` */
+    pub fn read_until(s: &mut String, delim: char) {
+        tty::ldisc::read_until(s, delim)
+    }
+
+    /* Edits a line with the shared history, see `editor::LineEditor`; the line ends with '\n' */
+    pub fn readline_with_prompt(prompt: &str, s: &mut String) {
+        match EDITOR.try_lock() {
+            Some(mut editor) => editor.read_line(prompt, s),
+            /* A prompt inside a prompt, e.g. the debugger on panic, gets an editor of its own */
+            None => LineEditor::with_history(0).read_line(prompt, s)
+        }
+    }
+
+    #[inline(always)]
+    pub fn readline(s: &mut String) {
+        readline_with_prompt("", s)
+    }
+
+    /* Sets tab completion of `readline` */
+    pub fn set_completer(completer: impl Completer + 'static) {
+        EDITOR.lock().set_completer(completer)
+    }
+
+}
+
+#[cfg(all(feature = "keyboard", feature = "allocator"))]
+pub use private::*;
+
+#[cfg(all(feature = "keyboard", feature = "allocator"))]
+pub use decoder::Decoder;
+
+#[cfg(all(feature = "keyboard", feature = "allocator"))]
+pub use editor::{LineEditor, Completer};
+
+#[cfg(all(feature = "keyboard", feature = "allocator"))]
+pub use handler::{Handler, HandlerHandle, Priority, Propagation, PRIORITY_HIGH, PRIORITY_NORMAL, PRIORITY_LOW, register_handler};
+
+#[cfg(all(feature = "keyboard", feature = "allocator"))]
+pub use hotkey::{register_hotkey, unregister_hotkey, interrupted, take_interrupt};
+
+#[cfg(all(feature = "keyboard", feature = "allocator", feature = "time"))]
+pub use repeat::{set_repeat, disable_repeat, repeat, DEFAULT_DELAY, DEFAULT_INTERVAL};
+
+#[cfg(all(feature = "keyboard", feature = "allocator"))]
+pub use queue::{EventStream, QUEUE_CAPACITY, next_event, try_next_event, event_stream, dropped};
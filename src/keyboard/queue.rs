@@ -0,0 +1,52 @@
+/****************************************************************/
+//                            Uses                              //
+/****************************************************************/
+
+use crate::handler::Events;
+pub use crate::handler::QUEUE_CAPACITY;
+use super::KeyEvent;
+
+/****************************************************************/
+//                            Types                             //
+/****************************************************************/
+
+/* Asynchronous source of key events, every event is passed through the handlers before being yielded */
+pub type EventStream = crate::handler::EventStream <KeyEvent>;
+
+/****************************************************************/
+//                           Statics                            //
+/****************************************************************/
+
+static EVENTS: Events <KeyEvent> = Events::new(&super::handler::HANDLERS);
+
+/****************************************************************/
+//                     Other functions                          //
+/****************************************************************/
+
+pub(super) fn init() {
+    EVENTS.init();
+}
+
+/* Called from the keyboard IRQ, never blocks */
+pub(super) fn push(event: KeyEvent) {
+    EVENTS.push(event);
+}
+
+/* Returns the oldest event after running the handlers on it, or `None` if there is none yet */
+pub fn try_next_event() -> Option <KeyEvent> {
+    EVENTS.try_next()
+}
+
+/* Waits for an event and runs the handlers on it */
+pub fn next_event() -> KeyEvent {
+    EVENTS.next()
+}
+
+pub fn event_stream() -> EventStream {
+    EVENTS.stream()
+}
+
+/* How many events were lost because the queue was full */
+pub fn dropped() -> usize {
+    EVENTS.dropped()
+}
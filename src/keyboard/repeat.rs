@@ -0,0 +1,106 @@
+/****************************************************************/
+//                            Uses                              //
+/****************************************************************/
+
+use spin::Mutex;
+use x86_64::instructions::interrupts;
+use crate::time::{self, Time};
+use super::{queue, Key, KeyEvent, KeyState};
+
+/****************************************************************/
+//                         Constants                            //
+/****************************************************************/
+
+pub const DEFAULT_DELAY: Time = Time::milliseconds(500);
+pub const DEFAULT_INTERVAL: Time = Time::milliseconds(50);
+
+/****************************************************************/
+//                            Types                             //
+/****************************************************************/
+
+struct Repeat {
+    enabled: bool,
+    delay: Time,    //< From the press to the first repeat
+    interval: Time, //< Between repeats
+    held: Option <(KeyEvent, Time)> //< Press being repeated and when the next repeat is due
+}
+
+/****************************************************************/
+//                           Statics                            //
+/****************************************************************/
+
+static REPEAT: Mutex <Repeat> = Mutex::new(Repeat {
+    enabled: true,
+    delay: DEFAULT_DELAY,
+    interval: DEFAULT_INTERVAL,
+    held: None
+});
+
+/****************************************************************/
+//                     Other functions                          //
+/****************************************************************/
+
+/* Modifiers and locks are never repeated */
+fn repeats(key: Key) -> bool {
+    !matches!(key,
+        Key::ShiftLeft | Key::ShiftRight | Key::ControlLeft | Key::ControlRight | Key::AltLeft | Key::AltRight |
+        Key::MetaLeft | Key::MetaRight | Key::CapsLock | Key::NumLock | Key::ScrollLock |
+        Key::SysRq | Key::PrintScreen | Key::Pause)
+}
+
+/* Called by the keyboard IRQ for every decoded event; `first` tells a real press from a typematic one.
+   Returns whether the event should be dropped, which happens to typematic presses while the repeat is ours */
+pub(super) fn isr(event: KeyEvent, first: bool) -> bool {
+    let mut repeat = REPEAT.lock();
+    if !repeat.enabled { return false }
+    match event.state {
+        KeyState::Press if !first => return true,
+        KeyState::Press if repeats(event.key) => {
+            let due = event.time + repeat.delay;
+            repeat.held = Some((event, due));
+        },
+        KeyState::Release if matches!(repeat.held, Some((held, _)) if held.key == event.key) => repeat.held = None,
+        _ => { }
+    }
+    false
+}
+
+/* Called from the timer IRQ */
+pub fn timer_isr() {
+    let mut repeat = REPEAT.lock();
+    let (held, due) = match repeat.held {
+        Some(held) => held,
+        None => return
+    };
+    let now = time::now();
+    if now < due { return }
+    repeat.held = Some((held, now + repeat.interval));
+    queue::push(KeyEvent { time: now, repeat: true, modifiers: super::modifiers(), ..held });
+}
+
+/* Repeats a held key after `delay`, every `interval`; the typematic repeats of the keyboard are dropped meanwhile */
+pub fn set_repeat(delay: Time, interval: Time) {
+    interrupts::without_interrupts(|| {
+        let mut repeat = REPEAT.lock();
+        repeat.enabled = true;
+        repeat.delay = delay;
+        repeat.interval = interval;
+    })
+}
+
+/* Hands repeating back to the keyboard, whose repeats still come with `KeyEvent::repeat` set */
+pub fn disable_repeat() {
+    interrupts::without_interrupts(|| {
+        let mut repeat = REPEAT.lock();
+        repeat.enabled = false;
+        repeat.held = None;
+    })
+}
+
+/* Delay and interval, or `None` if the keyboard repeats by itself */
+pub fn repeat() -> Option <(Time, Time)> {
+    interrupts::without_interrupts(|| {
+        let repeat = REPEAT.lock();
+        if repeat.enabled { Some((repeat.delay, repeat.interval)) } else { None }
+    })
+}
@@ -18,8 +18,16 @@ pub mod tty;
 
 pub mod idt;
 
+#[cfg(feature = "backtrace")]
+pub mod backtrace;
+
+#[cfg(feature = "serial")]
+pub mod serial;
+
 pub mod gdt;
 
+pub mod ps2;
+
 #[cfg(feature = "pci")]
 pub mod pci;
 
@@ -35,32 +43,68 @@ pub mod page;
 #[cfg(feature = "time")]
 pub mod time;
 
+#[cfg(feature = "time")]
+pub mod rtc;
+
 #[cfg(all(feature = "hash", feature = "allocator"))]
 pub mod hash;
 
 #[cfg(feature = "enum")]
 pub mod r#enum;
 
+#[cfg(feature = "allocator")]
+pub mod handler;
+
 #[cfg(all(feature = "keyboard", feature = "allocator"))]
 pub mod keyboard;
 
+#[cfg(all(feature = "mouse", feature = "allocator"))]
+pub mod mouse;
+
 #[cfg(feature = "allocator")]
 pub mod allocator;
 
+/* Always built for `log_or_println!`, the logger itself needs `log` and `allocator` */
+pub mod log;
+
 #[cfg(feature = "allocator")]
 pub extern crate alloc;
 
 pub fn init(boot_info: &'static bootloader::BootInfo) {
+    tty::vga::init(boot_info.physical_memory_offset);
+
     gdt::init();
 
     idt::init();
     unsafe { idt::PICS.lock().initialize() };
+
+    #[cfg(feature = "serial")]
+    serial::init();
+
+    #[cfg(feature = "time")] {
+        time::init();
+        rtc::init();
+    }
+
+    match ps2::init() {
+        Ok(ps2::Ports { second_error: Some(error), .. }) => println!("PS/2 aux port disabled: {:?}", error),
+        Ok(_) => { },
+        Err(error) => println!("PS/2 controller initialization failed: {:?}", error)
+    }
+
     x86_64::instructions::interrupts::enable();
 
     #[cfg(feature = "allocator")] {
         let mut mapper = unsafe { allocator::frame::init(x86_64::VirtAddr::new(boot_info.physical_memory_offset)) };
         let mut frame_allocator = unsafe { allocator::frame::BootInfoFrameAllocator::new(&boot_info.memory_map) };
         allocator::init_heap(&mut mapper, &mut frame_allocator).expect("Heap initialization failed");
+        tty::set_scrollback_depth(tty::DEFAULT_SCROLLBACK_DEPTH);
+
+        #[cfg(feature = "keyboard")]
+        keyboard::init();
+
+        #[cfg(feature = "mouse")]
+        if let Err(error) = mouse::init() { println!("PS/2 mouse initialization failed: {:?}", error) }
     }
 
     #[cfg(all(feature = "page", feature = "enum"))]
@@ -76,8 +120,23 @@ pub fn exit() -> ! {
 
 #[panic_handler]
 fn panic(info: &core::panic::PanicInfo) -> ! {
-    tty::set_color(tty::VGA::make(tty::Color::LightRed, tty::Color::Black));
-    println!("{}", info);
+    /* The console may be locked by the code that panicked, so only the emergency path is used here */
+    let depth = tty::emergency::enter();
+    if depth > 1 {
+        if depth == 2 {
+            tty::emergency::write_vga("Panic while panicking! Halting.");
+            #[cfg(feature = "serial")]
+            serial::_emergency_print(format_args!("Panic while panicking: {}\n", info));
+        }
+        x86_64::instructions::interrupts::disable();
+        loop { x86_64::instructions::hlt() }
+    }
+
+    tty::emergency::prepare();
+    emergency_println!("{}", info);
+
+    #[cfg(feature = "backtrace")]
+    emergency_print!("{}", backtrace::take_exception().unwrap_or_else(backtrace::Backtrace::capture));
 
     #[cfg(all(feature = "oll", feature = "hash", feature = "keyboard"))]
     if crate::oll::is_debug_mode_on() {
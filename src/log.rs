@@ -0,0 +1,260 @@
+#[cfg(all(feature = "log", feature = "allocator"))]
+mod private {
+    /****************************************************************/
+    //                            Uses                              //
+    /****************************************************************/
+
+    use alloc::{
+        vec::Vec,
+        string::String
+    };
+    use core::fmt;
+    use spin::Mutex;
+    use x86_64::instructions::interrupts;
+    use crate::{
+        print,
+        tty::{Sgr, Color, RESET}
+    };
+
+    /****************************************************************/
+    //                         Constants                            //
+    /****************************************************************/
+
+    /* How many records the dmesg ring keeps */
+    pub const DMESG_CAPACITY: usize = 128;
+
+    pub const DEFAULT_LEVEL: Level = Level::Debug;
+    pub const DEFAULT_CONSOLE_LEVEL: Level = Level::Info;
+
+    /****************************************************************/
+    //                            Types                             //
+    /****************************************************************/
+
+    #[allow(dead_code)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+    #[repr(u8)]
+    pub enum Level {
+        Off, //< Only for filters: nothing passes
+        Error,
+        Warn,
+        Info,
+        Debug,
+        Trace
+    }
+
+    impl Level {
+        pub const fn name(self) -> &'static str {
+            match self {
+                Level::Off => "OFF",
+                Level::Error => "ERROR",
+                Level::Warn => "WARN",
+                Level::Info => "INFO",
+                Level::Debug => "DEBUG",
+                Level::Trace => "TRACE"
+            }
+        }
+
+        /* Color of the records on the console, errors look like panics */
+        pub const fn color(self) -> Color {
+            match self {
+                Level::Off => Color::Default,
+                Level::Error => Color::LightRed,
+                Level::Warn => Color::Pink,
+                Level::Info => Color::LightGreen,
+                Level::Debug => Color::LightCyan,
+                Level::Trace => Color::DarkGray
+            }
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct Record {
+        pub level: Level,
+        pub time: u64, //< Microseconds since boot, always 0 unless the `time` feature is enabled
+        pub module: &'static str,
+        pub message: String
+    }
+
+    impl fmt::Display for Record {
+        fn fmt(&self, f: &mut fmt::Formatter <'_>) -> fmt::Result {
+            write!(f, "[{:>5}.{:06}] {:<5} {}: {}", self.time / 1_000_000, self.time % 1_000_000, self.level.name(), self.module, self.message)
+        }
+    }
+
+    struct Logger {
+        level: Level,
+        console: Level,
+        modules: Vec <(&'static str, Level)>,
+        records: Vec <Record>,
+        head: usize //< Index of the oldest record once the ring is full
+    }
+
+    impl Logger {
+        const fn new() -> Self {
+            Logger {
+                level: DEFAULT_LEVEL,
+                console: DEFAULT_CONSOLE_LEVEL,
+                modules: Vec::new(),
+                records: Vec::new(),
+                head: 0
+            }
+        }
+
+        /* The longest matching module prefix wins */
+        fn level_of(&self, module: &str) -> Level {
+            self.modules.iter()
+                .filter(|(prefix, _)| module.starts_with(prefix))
+                .max_by_key(|(prefix, _)| prefix.len())
+                .map_or(self.level, |&(_, level)| level)
+        }
+
+        fn push(&mut self, record: Record) {
+            if self.records.len() < DMESG_CAPACITY {
+                self.records.push(record);
+            } else {
+                self.records[self.head] = record;
+                self.head = (self.head + 1) % DMESG_CAPACITY;
+            }
+        }
+
+        fn iter(&self) -> impl Iterator <Item = &Record> {
+            let (new, old) = self.records.split_at(self.head);
+            old.iter().chain(new.iter())
+        }
+    }
+
+    /****************************************************************/
+    //                           Macros                             //
+    /****************************************************************/
+
+    #[macro_export]
+    macro_rules! log {
+        ($level:expr, $($arg:tt)*) => ($crate::log::_log($level, module_path!(), format_args!($($arg)*)));
+    }
+
+    #[macro_export]
+    macro_rules! log_error {
+        ($($arg:tt)*) => ($crate::log!($crate::log::Level::Error, $($arg)*));
+    }
+
+    #[macro_export]
+    macro_rules! log_warn {
+        ($($arg:tt)*) => ($crate::log!($crate::log::Level::Warn, $($arg)*));
+    }
+
+    #[macro_export]
+    macro_rules! log_info {
+        ($($arg:tt)*) => ($crate::log!($crate::log::Level::Info, $($arg)*));
+    }
+
+    #[macro_export]
+    macro_rules! log_debug {
+        ($($arg:tt)*) => ($crate::log!($crate::log::Level::Debug, $($arg)*));
+    }
+
+    #[macro_export]
+    macro_rules! log_trace {
+        ($($arg:tt)*) => ($crate::log!($crate::log::Level::Trace, $($arg)*));
+    }
+
+    /****************************************************************/
+    //                           Statics                            //
+    /****************************************************************/
+
+    static LOGGER: Mutex <Logger> = Mutex::new(Logger::new());
+
+    /****************************************************************/
+    //                     Other functions                          //
+    /****************************************************************/
+
+    /* Ticks have no fixed length since the PIT rate is configurable, so the time is taken instead */
+    #[cfg(feature = "time")]
+    fn now() -> u64 {
+        crate::time::now().into()
+    }
+
+    #[cfg(not(feature = "time"))]
+    fn now() -> u64 {
+        0
+    }
+
+    /* Records above `level` are dropped unless their module has its own level */
+    pub fn set_level(level: Level) {
+        interrupts::without_interrupts(|| LOGGER.lock().level = level)
+    }
+
+    /* Sets level for all modules whose path starts with `module`, e.g. "os::hdd" */
+    pub fn set_module_level(module: &'static str, level: Level) {
+        interrupts::without_interrupts(|| {
+            let mut logger = LOGGER.lock();
+            match logger.modules.iter_mut().find(|(prefix, _)| *prefix == module) {
+                Some(entry) => entry.1 = level,
+                None => logger.modules.push((module, level))
+            }
+        })
+    }
+
+    /* Records above `level` are kept in the ring only, without printing */
+    pub fn set_console_level(level: Level) {
+        interrupts::without_interrupts(|| LOGGER.lock().console = level)
+    }
+
+    pub fn enabled(level: Level, module: &str) -> bool {
+        level != Level::Off && interrupts::without_interrupts(|| level <= LOGGER.lock().level_of(module))
+    }
+
+    #[doc(hidden)]
+    pub fn _log(level: Level, module: &'static str, args: fmt::Arguments) {
+        if !enabled(level, module) { return }
+        let record = Record {
+            level,
+            time: now(),
+            module,
+            message: alloc::fmt::format(args)
+        };
+        if level <= interrupts::without_interrupts(|| LOGGER.lock().console) {
+            print!("{}{}{}\n", Sgr(level.color(), Color::Default), record, RESET);
+        }
+        interrupts::without_interrupts(|| LOGGER.lock().push(record));
+    }
+
+    /* Prints all the records from the ring, oldest first */
+    pub fn dmesg() {
+        interrupts::without_interrupts(|| {
+            for record in LOGGER.lock().iter() {
+                print!("{}{}{}\n", Sgr(record.level.color(), Color::Default), record, RESET);
+            }
+        })
+    }
+
+    /* Calls `f` for every record from the ring, oldest first */
+    pub fn for_each(mut f: impl FnMut(&Record)) {
+        interrupts::without_interrupts(|| {
+            for record in LOGGER.lock().iter() { f(record) }
+        })
+    }
+
+    pub fn clear() {
+        interrupts::without_interrupts(|| {
+            let mut logger = LOGGER.lock();
+            logger.records.clear();
+            logger.head = 0;
+        })
+    }
+}
+
+#[cfg(all(feature = "log", feature = "allocator"))]
+pub use private::*;
+
+/* `log_info!` when logging is available, `println!` otherwise */
+#[cfg(all(feature = "log", feature = "allocator"))]
+#[macro_export]
+macro_rules! log_or_println {
+    ($($arg:tt)*) => ($crate::log_info!($($arg)*));
+}
+
+#[cfg(not(all(feature = "log", feature = "allocator")))]
+#[macro_export]
+macro_rules! log_or_println {
+    ($($arg:tt)*) => ($crate::println!($($arg)*));
+}
@@ -0,0 +1,265 @@
+/****************************************************************/
+//                            Uses                              //
+/****************************************************************/
+
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use spin::Mutex;
+use x86_64::instructions::{
+    interrupts,
+    port::Port
+};
+use crate::{
+    handler::{Events, Handlers},
+    idt, ps2, tty
+};
+pub use crate::handler::{Handler, Priority, Propagation, PRIORITY_HIGH, PRIORITY_NORMAL, PRIORITY_LOW, QUEUE_CAPACITY};
+
+/****************************************************************/
+//                         Constants                            //
+/****************************************************************/
+
+const ACK: u8 = 0xFA;
+const RESEND: u8 = 0xFE;
+
+const SET_DEFAULTS: u8 = 0xF6;
+const SET_SAMPLE_RATE: u8 = 0xF3;
+const IDENTIFY: u8 = 0xF2;
+const ENABLE_REPORTING: u8 = 0xF4;
+
+/* Sample rates that switch an IntelliMouse into the 4-byte mode */
+const WHEEL_KNOCK: [u8; 3] = [200, 100, 80];
+
+const ID_WHEEL: u8 = 0x03;
+
+const MAX_RESENDS: u8 = 3;
+
+/* First packet byte */
+const LEFT: u8 = 0x01;
+const RIGHT: u8 = 0x02;
+const MIDDLE: u8 = 0x04;
+const ALWAYS_ONE: u8 = 0x08;
+const X_SIGN: u8 = 0x10;
+const Y_SIGN: u8 = 0x20;
+const X_OVERFLOW: u8 = 0x40;
+const Y_OVERFLOW: u8 = 0x80;
+
+/****************************************************************/
+//                            Types                             //
+/****************************************************************/
+
+pub type HandlerHandle = crate::handler::HandlerHandle <MouseEvent>;
+
+/* Asynchronous source of mouse events, every event is passed through the handlers before being yielded */
+pub type EventStream = crate::handler::EventStream <MouseEvent>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum MouseButton {
+    Left = LEFT,
+    Right = RIGHT,
+    Middle = MIDDLE
+}
+
+/* One packet: motion since the previous one and the buttons after it */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MouseEvent {
+    pub dx: i16,
+    pub dy: i16,  //< Grows downwards, as the screen rows do
+    pub wheel: i8, //< Positive when scrolled towards the user
+    buttons: u8,
+    changed: u8   //< Buttons that went up or down with this packet
+}
+
+impl MouseEvent {
+    pub fn is_pressed(&self, button: MouseButton) -> bool {
+        self.buttons & button as u8 != 0
+    }
+
+    /* Whether `button` went down with this packet */
+    pub fn pressed(&self, button: MouseButton) -> bool {
+        self.changed & self.buttons & button as u8 != 0
+    }
+
+    /* Whether `button` went up with this packet */
+    pub fn released(&self, button: MouseButton) -> bool {
+        self.changed & !self.buttons & button as u8 != 0
+    }
+
+    pub fn is_move(&self) -> bool {
+        self.dx != 0 || self.dy != 0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseError {
+    NoPort,                           //< The controller has no aux port
+    Controller(ps2::ControllerError),
+    NoAck(u8)                         //< The mouse answered a command with this instead of ACK
+}
+
+impl From <ps2::ControllerError> for MouseError {
+    fn from(error: ps2::ControllerError) -> Self {
+        MouseError::Controller(error)
+    }
+}
+
+/* Collects packet bytes coming one per IRQ */
+struct Packet {
+    bytes: [u8; 4],
+    len: usize,
+    size: usize //< 3, or 4 with a wheel
+}
+
+impl Packet {
+    const fn new() -> Self {
+        Packet {
+            bytes: [0; 4],
+            len: 0,
+            size: 3
+        }
+    }
+
+    fn feed(&mut self, byte: u8) -> Option <[u8; 4]> {
+        /* A first byte without the always-one bit means we lost sync, wait for the next packet */
+        if self.len == 0 && byte & ALWAYS_ONE == 0 { return None }
+        self.bytes[self.len] = byte;
+        self.len += 1;
+        if self.len < self.size { return None }
+        self.len = 0;
+        Some(self.bytes)
+    }
+}
+
+/****************************************************************/
+//                           Statics                            //
+/****************************************************************/
+
+static HANDLERS: Handlers <MouseEvent> = Handlers::new();
+
+static PACKET: Mutex <Packet> = Mutex::new(Packet::new());
+
+static EVENTS: Events <MouseEvent> = Events::new(&HANDLERS);
+
+static BUTTONS: AtomicU8 = AtomicU8::new(0);
+
+static WHEEL: AtomicBool = AtomicBool::new(false);
+
+/* Whether the IRQ moves the text-mode cursor */
+static POINTER: AtomicBool = AtomicBool::new(false);
+
+/****************************************************************/
+//                     Other functions                          //
+/****************************************************************/
+
+fn decode(bytes: [u8; 4], wheel: bool) -> MouseEvent {
+    let flags = bytes[0];
+    let mut dx = bytes[1] as i16;
+    let mut dy = bytes[2] as i16;
+    if flags & X_SIGN != 0 { dx -= 0x100 }
+    if flags & Y_SIGN != 0 { dy -= 0x100 }
+    /* Overflowed motion is garbage */
+    if flags & X_OVERFLOW != 0 { dx = 0 }
+    if flags & Y_OVERFLOW != 0 { dy = 0 }
+    /* The low nibble is a signed wheel delta, the upper bits are extra buttons on 5-button mice */
+    let wheel = if wheel { ((bytes[3] << 4) as i8) >> 4 } else { 0 };
+    let buttons = flags & (LEFT | RIGHT | MIDDLE);
+    MouseEvent {
+        dx,
+        dy: -dy,
+        wheel,
+        buttons,
+        changed: buttons ^ BUTTONS.swap(buttons, Ordering::Relaxed)
+    }
+}
+
+pub fn mouse_isr() {
+    let byte: u8 = unsafe { Port::new(ps2::DATA_PORT).read() };
+    let bytes = match PACKET.lock().feed(byte) {
+        Some(bytes) => bytes,
+        None => return
+    };
+    let event = decode(bytes, WHEEL.load(Ordering::Relaxed));
+    if POINTER.load(Ordering::Relaxed) && event.is_move() { tty::move_pointer(event.dx as i32, event.dy as i32) }
+    EVENTS.push(event);
+}
+
+/* Sends a command byte to the mouse, polling for the ACK */
+unsafe fn command(byte: u8) -> Result <(), MouseError> {
+    for _ in 0..=MAX_RESENDS {
+        ps2::write_second(byte)?;
+        match ps2::read_second()? {
+            ACK => return Ok(()),
+            RESEND => continue,
+            other => return Err(MouseError::NoAck(other))
+        }
+    }
+    Err(MouseError::NoAck(RESEND))
+}
+
+unsafe fn set_sample_rate(rate: u8) -> Result <(), MouseError> {
+    command(SET_SAMPLE_RATE)?;
+    command(rate)
+}
+
+/* Enables the mouse on the aux port, with the wheel if it has one, and unmasks IRQ12.
+   Needs `ps2::init` to have found the second port */
+pub fn init() -> Result <(), MouseError> {
+    if !ps2::has_second_port() { return Err(MouseError::NoPort) }
+    EVENTS.init();
+    interrupts::without_interrupts(|| unsafe {
+        command(SET_DEFAULTS)?;
+        for &rate in WHEEL_KNOCK.iter() { set_sample_rate(rate)? }
+        command(IDENTIFY)?;
+        let wheel = ps2::read_second()? == ID_WHEEL;
+        WHEEL.store(wheel, Ordering::Relaxed);
+        PACKET.lock().size = if wheel { 4 } else { 3 };
+        /* The knock left the rate at 80 */
+        set_sample_rate(100)?;
+        command(ENABLE_REPORTING)?;
+        idt::unmask(ps2::MOUSE_IRQ);
+        Ok(())
+    })
+}
+
+/* Returns the oldest event after running the handlers on it, or `None` if there is none yet */
+pub fn try_next_event() -> Option <MouseEvent> {
+    EVENTS.try_next()
+}
+
+/* Waits for an event and runs the handlers on it */
+pub fn next_event() -> MouseEvent {
+    EVENTS.next()
+}
+
+pub fn event_stream() -> EventStream {
+    EVENTS.stream()
+}
+
+/* How many events were lost because the queue was full */
+pub fn dropped() -> usize {
+    EVENTS.dropped()
+}
+
+/* Same as `keyboard::register_handler`: higher priorities first, `Stop` hides the event from the rest */
+pub fn register_handler(priority: Priority, handler: impl Handler <MouseEvent> + 'static) -> HandlerHandle {
+    HANDLERS.register(priority, handler)
+}
+
+pub fn has_wheel() -> bool {
+    WHEEL.load(Ordering::Relaxed)
+}
+
+pub fn is_pressed(button: MouseButton) -> bool {
+    BUTTONS.load(Ordering::Relaxed) & button as u8 != 0
+}
+
+/* Shows the text-mode cursor and lets the IRQ move it */
+pub fn enable_pointer() {
+    POINTER.store(true, Ordering::Relaxed);
+    tty::show_pointer();
+}
+
+pub fn disable_pointer() {
+    POINTER.store(false, Ordering::Relaxed);
+    tty::hide_pointer();
+}
@@ -2,6 +2,7 @@
 mod private {
 
 	use alloc::{
+		format,
 		string::String,
 		vec::Vec,
 	};
@@ -10,7 +11,7 @@ mod private {
 		iter::Iterator
 	};
 	use crate::{
-		tty,
+		tty::{Sgr, Color, RESET},
 		keyboard,
 		print,
 		println,
@@ -71,8 +72,7 @@ mod private {
 
 	impl <T: Iterator + Clone> DebugContainerIteratorTrait for DebugContainerIterator <T> where T::Item: Debug {
 		fn print(&mut self) {
-			tty::set_color(tty::VGA::make(tty::Color::LightBlue, tty::Color::Default));
-			println!("{:?}", self.iter.clone().nth(self.index).unwrap())
+			println!("{}{:?}", Sgr(Color::LightBlue, Color::Default), self.iter.clone().nth(self.index).unwrap())
 		}
 
 		fn next(&mut self) {
@@ -155,8 +155,7 @@ mod private {
 				}
 			}
 			if indent != -1 {
-				tty::set_color(tty::VGA::make(tty::Color::LightBlue, tty::Color::Default));
-				println!("{}", self.cnt[index].string);
+				println!("{}{}", Sgr(Color::LightBlue, Color::Default), self.cnt[index].string);
 				return true
 			}
 			indent = -1;
@@ -167,7 +166,7 @@ mod private {
 				}
 			}
 			if indent != -1 {
-				tty::set_color(tty::VGA::make(tty::Color::Green, tty::Color::Default));
+				print!("{}", Sgr(Color::Green, Color::Default));
 				self.its[index].print();
 				return true
 			}
@@ -176,8 +175,7 @@ mod private {
 
 		fn _td_template(&mut self, s: &'static str) {
 			if is_skipping() { return }
-			tty::set_color(tty::VGA::make(tty::Color::LightGreen, tty::Color::Default));
-			print!("{}ing sub-block", s);
+			print!("{}{}ing sub-block", Sgr(Color::LightGreen, Color::Default), s);
 			if self.indent > 1 {
 				let x = self.indent % 10;
 				print!("({}-", self.indent);
@@ -192,8 +190,7 @@ mod private {
 				}
 				print!(")")
 			}
-			tty::set_color(tty::DEFAULT);
-			println!();
+			println!("{}", RESET);
 		}
 
 		pub fn up(&mut self) {
@@ -224,8 +221,7 @@ mod private {
 
 		pub fn cycle() {
 			if is_skipping() { return }
-			tty::set_color(tty::VGA::make(tty::Color::LightGreen, tty::Color::Default));
-			println!("Loop repeats")
+			println!("{}Loop repeats", Sgr(Color::LightGreen, Color::Default))
 		}
 
 		pub fn clear(&mut self) {
@@ -233,15 +229,11 @@ mod private {
 			if self.initial != 0 {
 				if self.indent - 1 != self.initial { return }
 				self.indent -= 1;
-				tty::set_color(tty::VGA::make(tty::Color::Green, tty::Color::Default));
-				println!("Control is returned.");
-				tty::set_color(tty::DEFAULT);
+				println!("{}Control is returned.{}", Sgr(Color::Green, Color::Default), RESET);
 				self.initial = 0;
 			} else {
 				if self.indent != self.initial { return }
-				tty::set_color(tty::VGA::make(tty::Color::Green, tty::Color::Default));
-				println!("Leaving oll.");
-				tty::set_color(tty::DEFAULT);
+				println!("{}Leaving oll.{}", Sgr(Color::Green, Color::Default), RESET);
 				self.vec.clear();
 				self.cnt.clear();
 				self.its.clear();
@@ -257,12 +249,9 @@ mod private {
 			if self.init {
 				self.initial = self.indent;
 				self.indent += 1;
-				tty::set_color(tty::VGA::make(tty::Color::Green, tty::Color::Default));
-				println!("Control was taken by other `debug!` block")
+				println!("{}Control was taken by other `debug!` block", Sgr(Color::Green, Color::Default))
 			} else {
-				tty::set_color(tty::VGA::make(tty::Color::Green, tty::Color::Default));
-				println!("Welcome to the OS-level debugger (oll)!");
-				tty::set_color(tty::DEFAULT);
+				println!("{}Welcome to the OS-level debugger (oll)!{}", Sgr(Color::Green, Color::Default), RESET);
 				self.init = true;
 				unsafe { USING |= 1 }
 			}
@@ -289,29 +278,18 @@ mod private {
 	pub fn take(expr: &str) {
 		if is_skipping() { return }
 		let w = || {
-			tty::set_color(tty::VGA::make(tty::Color::LightGreen, tty::Color::Default));
-			print!("Executing ");
-			tty::set_color(tty::VGA::make(tty::Color::LightRed, tty::Color::Default));
-			print!("`");
-			tty::set_color(tty::DEFAULT);
-			print!("{}", expr);
-			tty::set_color(tty::VGA::make(tty::Color::LightRed, tty::Color::Default));
-			println!("`");
+			println!("{}Executing {}`{}{}{}`", Sgr(Color::LightGreen, Color::Default), Sgr(Color::LightRed, Color::Default), RESET, expr, Sgr(Color::LightRed, Color::Default));
 		};
 		w();
 		if expr.starts_with("continue") { unsafe { DC.indent -= 1 } }
 		loop {
-			tty::set_color(tty::VGA::make(tty::Color::White, tty::Color::Default));
-			print!("> ");
-			tty::set_color(tty::DEFAULT);
 			let mut s = String::new();
-			keyboard::readline(&mut s);
-			if s == "\n" { continue }
+			keyboard::readline_with_prompt(&format!("{}> {}", Sgr(Color::White, Color::Default), RESET), &mut s);
+			if s.is_empty() || s == "\n" { continue }
 			if s == ":e\n" { break }
 			if s.starts_with(":d ") {
 				if !unsafe { DC.print(hash(&s[3..s.len() - 1])) } {
-					tty::set_color(tty::VGA::make(tty::Color::LightRed, tty::Color::Default));
-					println!("There's no variable with such name");
+					println!("{}There's no variable with such name", Sgr(Color::LightRed, Color::Default));
 				}
 				continue
 			}
@@ -324,14 +302,12 @@ mod private {
 				return
 			}
 			if s == ":h\n" {
-				tty::set_color(tty::VGA::make(tty::Color::Magenta, tty::Color::Default));
-				println!("Commands:\n\t:e - execute command\n\t:d <name> - show value of <name>\n\t:q - quit debugger\n\t:h - show this info\n\t:w - show currently executing line");
+				println!("{}Commands:\n\t:e - execute command\n\t:d <name> - show value of <name>\n\t:q - quit debugger\n\t:h - show this info\n\t:w - show currently executing line", Sgr(Color::Magenta, Color::Default));
 				continue
 			}
-			tty::set_color(tty::VGA::make(tty::Color::LightRed, tty::Color::Default));
-			println!("Unknown command; try ':h'!");
+			println!("{}Unknown command; try ':h'!", Sgr(Color::LightRed, Color::Default));
 		}
-		tty::set_color(tty::DEFAULT);
+		print!("{}", RESET);
 	}
 
 	pub static mut DC: DebugContainer = DebugContainer::new();
@@ -339,20 +315,12 @@ mod private {
 
 	pub fn print <T> (p: *const u8) where T: Debug {
 		if is_skipping() { return }
-		tty::set_color(tty::VGA::make(tty::Color::LightBlue, tty::Color::Default));
-		unsafe { println!("{:?}", *(p as *const T)) }
+		unsafe { println!("{}{:?}", Sgr(Color::LightBlue, Color::Default), *(p as *const T)) }
 	}
 
 	pub fn cycle(n: &'static str, msg: &'static str) {
 		if is_skipping() { return }
-		tty::set_color(tty::VGA::make(tty::Color::LightGreen, tty::Color::Default));
-		print!("Executing {}: ", msg);
-		tty::set_color(tty::VGA::make(tty::Color::LightRed, tty::Color::Default));
-		print!("`");
-		tty::set_color(tty::VGA::make(tty::Color::Yellow, tty::Color::Default));
-		print!("{}", n);
-		tty::set_color(tty::VGA::make(tty::Color::LightRed, tty::Color::Default));
-		println!("`");
+		println!("{}Executing {}: {}`{}{}{}`", Sgr(Color::LightGreen, Color::Default), msg, Sgr(Color::LightRed, Color::Default), Sgr(Color::Yellow, Color::Default), n, Sgr(Color::LightRed, Color::Default));
 	}
 
 	pub fn cfor(s: Hash) {
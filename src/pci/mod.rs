@@ -25,7 +25,6 @@ mod private {
         subclass::*,
         pib::*
     };
-    use crate::println;
 
     /****************************************************************/
     //                         Constants                            //
@@ -362,7 +361,7 @@ mod private {
     }
 
     pub unsafe fn print_dev(bus: u8, dev: u8, fun: u8, device: &Dev) {
-        println!("bus=0x{:x} dev=0x{:x} fun=0x{:x} venID=0x{:x} devID=0x{:x} class={} subClass={} pib={}", bus, dev, fun, device.option.vendorID, device.option.deviceID, device.classname(), device.subclass(), device.pib());
+        crate::log_or_println!("bus=0x{:x} dev=0x{:x} fun=0x{:x} venID=0x{:x} devID=0x{:x} class={} subClass={} pib={}", bus, dev, fun, device.option.vendorID, device.option.deviceID, device.classname(), device.subclass(), device.pib());
     }
 
     pub unsafe fn check_bus(bus: u8, device: &mut Dev) {
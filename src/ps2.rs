@@ -0,0 +1,262 @@
+/****************************************************************/
+//                            Uses                              //
+/****************************************************************/
+
+use core::sync::atomic::{AtomicBool, Ordering};
+use x86_64::instructions::{
+    hlt,
+    interrupts,
+    port::Port
+};
+use crate::idt;
+
+/****************************************************************/
+//                         Constants                            //
+/****************************************************************/
+
+pub const DATA_PORT: u16 = 0x60;
+pub const STATUS_PORT: u16 = 0x64;  //< Read
+pub const COMMAND_PORT: u16 = 0x64; //< Write
+
+/* Status register */
+pub const OUTPUT_FULL: u8 = 0x01;
+pub const INPUT_FULL: u8 = 0x02;
+pub const AUX_DATA: u8 = 0x20; //< The byte in the output buffer came from the second port
+
+/* Controller commands */
+const READ_CONFIG: u8 = 0x20;
+const WRITE_CONFIG: u8 = 0x60;
+const DISABLE_SECOND: u8 = 0xA7;
+const ENABLE_SECOND: u8 = 0xA8;
+const TEST_SECOND: u8 = 0xA9;
+const TEST_CONTROLLER: u8 = 0xAA;
+const TEST_FIRST: u8 = 0xAB;
+const DISABLE_FIRST: u8 = 0xAD;
+const ENABLE_FIRST: u8 = 0xAE;
+const WRITE_SECOND: u8 = 0xD4;
+const PULSE_RESET: u8 = 0xFE;
+
+/* Configuration byte */
+pub const FIRST_IRQ: u8 = 0x01;
+pub const SECOND_IRQ: u8 = 0x02;
+pub const FIRST_CLOCK_OFF: u8 = 0x10;
+pub const SECOND_CLOCK_OFF: u8 = 0x20;
+pub const TRANSLATION: u8 = 0x40; //< Set 2 scancodes are translated to set 1, which `keyboard` decodes
+
+pub const KEYBOARD_IRQ: u8 = 1;
+pub const MOUSE_IRQ: u8 = 12;
+
+const CONTROLLER_OK: u8 = 0x55;
+const PORT_OK: u8 = 0x00;
+
+/* Status polls before giving up on the controller */
+const ATTEMPTS: usize = 100_000;
+
+/* Bytes read at most while flushing stale output */
+const FLUSH_LIMIT: usize = 32;
+
+/****************************************************************/
+//                            Types                             //
+/****************************************************************/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControllerError {
+    Timeout,            //< The controller did not take or give a byte in time
+    ControllerTest(u8), //< Self-test answered with this instead of 0x55
+    FirstPort(u8),      //< Interface test of the keyboard port failed with this code
+    SecondPort(u8)      //< Interface test of the aux port failed with this code
+}
+
+/* Ports that passed the tests and were enabled */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ports {
+    pub first: bool,
+    pub second: bool,
+    pub second_error: Option <ControllerError> //< Why the aux port was left off although it exists
+}
+
+/****************************************************************/
+//                           Statics                            //
+/****************************************************************/
+
+static SECOND_PORT: AtomicBool = AtomicBool::new(false);
+
+/****************************************************************/
+//                     Other functions                          //
+/****************************************************************/
+
+pub unsafe fn status() -> u8 {
+    Port::new(STATUS_PORT).read()
+}
+
+unsafe fn wait_write() -> Result <(), ControllerError> {
+    for _ in 0..ATTEMPTS {
+        if status() & INPUT_FULL == 0 { return Ok(()) }
+        core::hint::spin_loop();
+    }
+    Err(ControllerError::Timeout)
+}
+
+unsafe fn wait_read() -> Result <(), ControllerError> {
+    for _ in 0..ATTEMPTS {
+        if status() & OUTPUT_FULL != 0 { return Ok(()) }
+        core::hint::spin_loop();
+    }
+    Err(ControllerError::Timeout)
+}
+
+pub unsafe fn write_command(command: u8) -> Result <(), ControllerError> {
+    wait_write()?;
+    Port::new(COMMAND_PORT).write(command);
+    Ok(())
+}
+
+/* Sends `byte` to the device on the first port */
+pub unsafe fn write_data(byte: u8) -> Result <(), ControllerError> {
+    wait_write()?;
+    Port::new(DATA_PORT).write(byte);
+    Ok(())
+}
+
+/* Sends `byte` to the device on the second port */
+pub unsafe fn write_second(byte: u8) -> Result <(), ControllerError> {
+    write_command(WRITE_SECOND)?;
+    write_data(byte)
+}
+
+/* Polls for a byte, only for when the IRQs are off */
+pub unsafe fn read_data() -> Result <u8, ControllerError> {
+    wait_read()?;
+    Ok(Port::new(DATA_PORT).read())
+}
+
+/* Polls for a byte from the second port, bytes of the keyboard are dropped meanwhile */
+pub unsafe fn read_second() -> Result <u8, ControllerError> {
+    for _ in 0..ATTEMPTS {
+        let status = status();
+        if status & OUTPUT_FULL != 0 {
+            let byte = Port::new(DATA_PORT).read();
+            if status & AUX_DATA != 0 { return Ok(byte) }
+        }
+        core::hint::spin_loop();
+    }
+    Err(ControllerError::Timeout)
+}
+
+pub unsafe fn read_config() -> Result <u8, ControllerError> {
+    write_command(READ_CONFIG)?;
+    read_data()
+}
+
+pub unsafe fn write_config(config: u8) -> Result <(), ControllerError> {
+    write_command(WRITE_CONFIG)?;
+    write_data(config)
+}
+
+unsafe fn flush() {
+    for _ in 0..FLUSH_LIMIT {
+        if status() & OUTPUT_FULL == 0 { break }
+        Port::<u8>::new(DATA_PORT).read();
+    }
+}
+
+/* Runs with interrupts off, so the answers are polled instead of reaching the keyboard IRQ.
+   Once the keyboard port is disabled, every way out leaves it enabled again */
+fn reset() -> Result <Ports, ControllerError> {
+    unsafe {
+        write_command(DISABLE_FIRST)?;
+        let result = configure();
+        if result.is_err() { restore_first() }
+        result
+    }
+}
+
+unsafe fn configure() -> Result <Ports, ControllerError> {
+    write_command(DISABLE_SECOND)?;
+    flush();
+
+    let mut config = read_config()?;
+    /* Disabling the second port stops its clock only if the port exists */
+    let mut dual = config & SECOND_CLOCK_OFF != 0;
+    config &= !(FIRST_IRQ | SECOND_IRQ);
+    config |= TRANSLATION;
+    write_config(config)?;
+
+    write_command(TEST_CONTROLLER)?;
+    match read_data()? {
+        CONTROLLER_OK => { },
+        code => return Err(ControllerError::ControllerTest(code))
+    }
+    /* Some controllers reset themselves during the test */
+    write_config(config)?;
+
+    if dual {
+        write_command(ENABLE_SECOND)?;
+        dual = read_config()? & SECOND_CLOCK_OFF == 0;
+        write_command(DISABLE_SECOND)?;
+    }
+
+    write_command(TEST_FIRST)?;
+    match read_data()? {
+        PORT_OK => { },
+        code => return Err(ControllerError::FirstPort(code))
+    }
+    /* A broken aux port only costs the mouse */
+    let mut second_error = None;
+    if dual {
+        write_command(TEST_SECOND)?;
+        match read_data()? {
+            PORT_OK => { },
+            code => {
+                second_error = Some(ControllerError::SecondPort(code));
+                dual = false;
+            }
+        }
+    }
+
+    write_command(ENABLE_FIRST)?;
+    config |= FIRST_IRQ;
+    config &= !FIRST_CLOCK_OFF;
+    if dual {
+        write_command(ENABLE_SECOND)?;
+        config |= SECOND_IRQ;
+        config &= !SECOND_CLOCK_OFF;
+    }
+    write_config(config)?;
+    flush();
+
+    Ok(Ports { first: true, second: dual, second_error })
+}
+
+/* Best effort after a failed `configure`, so the keyboard keeps working with whatever the BIOS set up */
+unsafe fn restore_first() {
+    write_command(ENABLE_FIRST).ok();
+    if let Ok(config) = read_config() {
+        write_config((config | FIRST_IRQ | TRANSLATION) & !FIRST_CLOCK_OFF).ok();
+    }
+    flush();
+}
+
+/* Brings the 8042 to a known state instead of trusting the BIOS: both ports tested and enabled
+   with IRQ1/IRQ12 on. The PIC line of IRQ12 is left to the mouse driver.
+   IRQ1 is unmasked even on error, since the keyboard port is enabled again then */
+pub fn init() -> Result <Ports, ControllerError> {
+    interrupts::without_interrupts(|| {
+        let result = reset();
+        SECOND_PORT.store(matches!(result, Ok(ports) if ports.second), Ordering::Relaxed);
+        idt::unmask(KEYBOARD_IRQ);
+        result
+    })
+}
+
+/* Whether `init` found the aux (mouse) port */
+pub fn has_second_port() -> bool {
+    SECOND_PORT.load(Ordering::Relaxed)
+}
+
+/* Resets the machine through the CPU reset line of the controller */
+pub fn reboot() -> ! {
+    interrupts::disable();
+    unsafe { write_command(PULSE_RESET).ok() };
+    loop { hlt() }
+}
@@ -0,0 +1,379 @@
+/****************************************************************/
+//                            Uses                              //
+/****************************************************************/
+
+use core::{
+    fmt,
+    sync::atomic::{AtomicU64, Ordering}
+};
+use spin::Mutex;
+use x86_64::instructions::{
+    interrupts,
+    port::Port
+};
+use crate::idt;
+
+/****************************************************************/
+//                         Constants                            //
+/****************************************************************/
+
+const ADDRESS_PORT: u16 = 0x70;
+const DATA_PORT: u16 = 0x71;
+
+/* Registers */
+const SECONDS: u8 = 0x00;
+const MINUTES: u8 = 0x02;
+const HOURS: u8 = 0x04;
+const DAY: u8 = 0x07;
+const MONTH: u8 = 0x08;
+const YEAR: u8 = 0x09;
+const STATUS_A: u8 = 0x0A;
+const STATUS_B: u8 = 0x0B;
+const STATUS_C: u8 = 0x0C;
+const CENTURY: u8 = 0x32; //< Not standard, but kept by most BIOSes
+
+/* Status A */
+const UPDATE_IN_PROGRESS: u8 = 0x80;
+const RATE_MASK: u8 = 0x0F;
+
+/* Status B */
+const HOURS_24: u8 = 0x02;
+const BINARY: u8 = 0x04;
+const UPDATE_INTERRUPT: u8 = 0x10;
+const PERIODIC_INTERRUPT: u8 = 0x40;
+const SET: u8 = 0x80; //< Stops updates while the clock is written
+
+/* Status C, reading it acknowledges the IRQ */
+const UPDATE_ENDED: u8 = 0x10;
+const PERIODIC: u8 = 0x40;
+
+/* Hour bit of PM in the 12-hour mode */
+const PM: u8 = 0x80;
+
+pub const RTC_IRQ: u8 = 8;
+
+/* Oscillator of the periodic interrupt, divided by 2^(rate - 1) */
+const BASE_FREQUENCY: u32 = 32768;
+
+/* Rates 1 and 2 are unreliable, 3 gives 8192 Hz and 15 gives 2 Hz */
+const MIN_RATE: u8 = 3;
+const MAX_RATE: u8 = 15;
+
+/* Used when the century register holds nothing sensible */
+const DEFAULT_CENTURY: u16 = 20;
+
+/* Years `set` accepts with a century register; timestamps start in 1970 and the register holds 19..=21 */
+const MIN_YEAR: u16 = 1970;
+const MAX_YEAR: u16 = 2199;
+
+const SECONDS_PER_DAY: u64 = 86400;
+
+/****************************************************************/
+//                            Types                             //
+/****************************************************************/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum RtcError {
+    InvalidDateTime,
+    UnsupportedYear //< Only years 1970..=2199 can be stored, or only those of `DEFAULT_CENTURY` without a century register
+}
+
+/* Wall-clock time in UTC, or whatever zone the BIOS clock is kept in */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DateTime {
+    pub year: u16,
+    pub month: u8, //< 1..=12
+    pub day: u8,   //< 1..=31
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8
+}
+
+impl DateTime {
+    pub const fn new(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> Self {
+        DateTime { year, month, day, hour, minute, second }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        (1..=12).contains(&self.month) && self.day >= 1 && self.day <= days_in_month(self.year, self.month) &&
+            self.hour < 24 && self.minute < 60 && self.second < 60 && self.year >= MIN_YEAR
+    }
+
+    /* Seconds since 1970-01-01 00:00:00 */
+    pub fn unix_timestamp(&self) -> u64 {
+        days_from_civil(self.year as i64, self.month as i64, self.day as i64) as u64 * SECONDS_PER_DAY +
+            self.hour as u64 * 3600 + self.minute as u64 * 60 + self.second as u64
+    }
+
+    pub fn from_unix_timestamp(timestamp: u64) -> Self {
+        let (year, month, day) = civil_from_days((timestamp / SECONDS_PER_DAY) as i64);
+        let seconds = timestamp % SECONDS_PER_DAY;
+        DateTime {
+            year: year as u16,
+            month: month as u8,
+            day: day as u8,
+            hour: (seconds / 3600) as u8,
+            minute: (seconds / 60 % 60) as u8,
+            second: (seconds % 60) as u8
+        }
+    }
+}
+
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02} {:02}:{:02}:{:02}", self.year, self.month, self.day, self.hour, self.minute, self.second)
+    }
+}
+
+/* Raw register values, compared between two reads to catch an update in the middle */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Registers {
+    second: u8,
+    minute: u8,
+    hour: u8,
+    day: u8,
+    month: u8,
+    year: u8,
+    century: u8
+}
+
+struct Cmos {
+    _private: ()
+}
+
+impl Cmos {
+    unsafe fn read(&mut self, register: u8) -> u8 {
+        Port::new(ADDRESS_PORT).write(register);
+        Port::new(DATA_PORT).read()
+    }
+
+    unsafe fn write(&mut self, register: u8, value: u8) {
+        Port::new(ADDRESS_PORT).write(register);
+        Port::new(DATA_PORT).write(value);
+    }
+
+    unsafe fn updating(&mut self) -> bool {
+        self.read(STATUS_A) & UPDATE_IN_PROGRESS != 0
+    }
+
+    unsafe fn registers(&mut self) -> Registers {
+        Registers {
+            second: self.read(SECONDS),
+            minute: self.read(MINUTES),
+            hour: self.read(HOURS),
+            day: self.read(DAY),
+            month: self.read(MONTH),
+            year: self.read(YEAR),
+            century: self.read(CENTURY)
+        }
+    }
+
+    /* Reads until two reads in a row outside of an update agree */
+    unsafe fn read_registers(&mut self) -> Registers {
+        let mut last = None;
+        loop {
+            while self.updating() { core::hint::spin_loop() }
+            let registers = self.registers();
+            if last == Some(registers) { return registers }
+            last = Some(registers);
+        }
+    }
+}
+
+/****************************************************************/
+//                           Statics                            //
+/****************************************************************/
+
+static CMOS: Mutex <Cmos> = Mutex::new(Cmos { _private: () });
+
+static UPDATES: AtomicU64 = AtomicU64::new(0);
+static PERIODIC_TICKS: AtomicU64 = AtomicU64::new(0);
+
+static UPDATE_HANDLER: Mutex <Option <fn()>> = Mutex::new(None);
+static PERIODIC_HANDLER: Mutex <Option <fn()>> = Mutex::new(None);
+
+/****************************************************************/
+//                     Other functions                          //
+/****************************************************************/
+
+const fn from_bcd(value: u8) -> u8 {
+    (value >> 4) * 10 + (value & 0x0F)
+}
+
+const fn to_bcd(value: u8) -> u8 {
+    (value / 10) << 4 | value % 10
+}
+
+fn is_leap(year: u16) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if is_leap(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31
+    }
+}
+
+/* Days since 1970-01-01, see Howard Hinnant's `days_from_civil` */
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/* Century of the century register, if it holds one */
+fn century(registers: &Registers, binary: bool) -> Option <u16> {
+    let century = if binary { registers.century } else { from_bcd(registers.century) };
+    if (19..=21).contains(&century) { Some(century as u16) } else { None }
+}
+
+/* Clears pending interrupts, so that IRQ8 is raised again once enabled */
+pub fn init() {
+    interrupts::without_interrupts(|| unsafe { CMOS.lock().read(STATUS_C) });
+}
+
+/* Current date and time of the battery clock */
+pub fn now() -> DateTime {
+    let (registers, status) = interrupts::without_interrupts(|| unsafe {
+        let mut cmos = CMOS.lock();
+        let registers = cmos.read_registers();
+        (registers, cmos.read(STATUS_B))
+    });
+    let binary = status & BINARY != 0;
+    let decode = |value: u8| if binary { value } else { from_bcd(value) };
+
+    let pm = registers.hour & PM != 0;
+    let mut hour = decode(registers.hour & !PM);
+    if status & HOURS_24 == 0 {
+        hour %= 12;
+        if pm { hour += 12 }
+    }
+    let century = century(&registers, binary).unwrap_or(DEFAULT_CENTURY);
+    DateTime {
+        year: century * 100 + decode(registers.year) as u16,
+        month: decode(registers.month),
+        day: decode(registers.day),
+        hour,
+        minute: decode(registers.minute),
+        second: decode(registers.second)
+    }
+}
+
+/* Seconds since 1970 by the battery clock */
+pub fn timestamp() -> u64 {
+    now().unix_timestamp()
+}
+
+/* Sets the battery clock, keeping the BCD or binary and 12 or 24-hour modes the BIOS chose */
+pub fn set(datetime: DateTime) -> Result <(), RtcError> {
+    if !(MIN_YEAR..=MAX_YEAR).contains(&datetime.year) { return Err(RtcError::UnsupportedYear) }
+    if !datetime.is_valid() { return Err(RtcError::InvalidDateTime) }
+    interrupts::without_interrupts(|| unsafe {
+        let mut cmos = CMOS.lock();
+        let status = cmos.read(STATUS_B);
+        let binary = status & BINARY != 0;
+        let encode = |value: u8| if binary { value } else { to_bcd(value) };
+
+        let hour = if status & HOURS_24 != 0 {
+            encode(datetime.hour)
+        } else {
+            let hour = match datetime.hour % 12 { 0 => 12, hour => hour };
+            encode(hour) | if datetime.hour >= 12 { PM } else { 0 }
+        };
+        /* Without the register the year is read back in `DEFAULT_CENTURY` */
+        let has_century = century(&cmos.registers(), binary).is_some();
+        if !has_century && datetime.year / 100 != DEFAULT_CENTURY { return Err(RtcError::UnsupportedYear) }
+
+        cmos.write(STATUS_B, status | SET);
+        cmos.write(SECONDS, encode(datetime.second));
+        cmos.write(MINUTES, encode(datetime.minute));
+        cmos.write(HOURS, hour);
+        cmos.write(DAY, encode(datetime.day));
+        cmos.write(MONTH, encode(datetime.month));
+        cmos.write(YEAR, encode((datetime.year % 100) as u8));
+        if has_century { cmos.write(CENTURY, encode((datetime.year / 100) as u8)) }
+        cmos.write(STATUS_B, status & !SET);
+        Ok(())
+    })
+}
+
+unsafe fn set_status_b(cmos: &mut Cmos, bits: u8, on: bool) {
+    let status = cmos.read(STATUS_B);
+    cmos.write(STATUS_B, if on { status | bits } else { status & !bits });
+    if cmos.read(STATUS_B) & (UPDATE_INTERRUPT | PERIODIC_INTERRUPT) != 0 { idt::unmask(RTC_IRQ) } else { idt::mask(RTC_IRQ) }
+}
+
+/* IRQ8 once a second, right after the clock was updated; `handler` runs in the IRQ */
+pub fn enable_update_interrupt(handler: Option <fn()>) {
+    interrupts::without_interrupts(|| {
+        *UPDATE_HANDLER.lock() = handler;
+        unsafe { set_status_b(&mut CMOS.lock(), UPDATE_INTERRUPT, true) }
+    })
+}
+
+pub fn disable_update_interrupt() {
+    interrupts::without_interrupts(|| unsafe { set_status_b(&mut CMOS.lock(), UPDATE_INTERRUPT, false) })
+}
+
+/* IRQ8 at about `frequency` Hz, rounded to a power of two in 2..=8192; returns the frequency set.
+   `handler` runs in the IRQ */
+pub fn enable_periodic_interrupt(frequency: u32, handler: Option <fn()>) -> u32 {
+    let mut rate = MAX_RATE;
+    while rate > MIN_RATE && BASE_FREQUENCY >> (rate - 1) < frequency { rate -= 1 }
+    interrupts::without_interrupts(|| unsafe {
+        *PERIODIC_HANDLER.lock() = handler;
+        let mut cmos = CMOS.lock();
+        let a = cmos.read(STATUS_A);
+        cmos.write(STATUS_A, (a & !RATE_MASK) | rate);
+        set_status_b(&mut cmos, PERIODIC_INTERRUPT, true);
+    });
+    BASE_FREQUENCY >> (rate - 1)
+}
+
+pub fn disable_periodic_interrupt() {
+    interrupts::without_interrupts(|| unsafe { set_status_b(&mut CMOS.lock(), PERIODIC_INTERRUPT, false) })
+}
+
+/* Update-ended interrupts so far */
+pub fn updates() -> u64 {
+    UPDATES.load(Ordering::Relaxed)
+}
+
+/* Periodic interrupts so far */
+pub fn periodic_ticks() -> u64 {
+    PERIODIC_TICKS.load(Ordering::Relaxed)
+}
+
+pub fn rtc_isr() {
+    let flags = unsafe { CMOS.lock().read(STATUS_C) };
+    if flags & UPDATE_ENDED != 0 {
+        UPDATES.fetch_add(1, Ordering::Relaxed);
+        let handler = *UPDATE_HANDLER.lock();
+        if let Some(handler) = handler { handler() }
+    }
+    if flags & PERIODIC != 0 {
+        PERIODIC_TICKS.fetch_add(1, Ordering::Relaxed);
+        let handler = *PERIODIC_HANDLER.lock();
+        if let Some(handler) = handler { handler() }
+    }
+}
@@ -0,0 +1,370 @@
+#[cfg(feature = "serial")]
+mod private {
+    /****************************************************************/
+    //                            Uses                              //
+    /****************************************************************/
+
+    use core::{
+        fmt,
+        sync::atomic::{AtomicBool, Ordering}
+    };
+    use spin::Mutex;
+    use x86_64::instructions::{
+        interrupts,
+        port::Port
+    };
+    use crate::idt;
+
+    /****************************************************************/
+    //                         Constants                            //
+    /****************************************************************/
+
+    pub const COM1: u16 = 0x3F8;
+    pub const COM2: u16 = 0x2F8;
+
+    pub const COM1_IRQ: u8 = 4;
+    pub const COM2_IRQ: u8 = 3;
+
+    /* UART input clock divided by 16 */
+    pub const MAX_BAUD: u32 = 115200;
+    pub const DEFAULT_BAUD: u32 = 38400;
+
+    pub const DEFAULT_LINE: LineControl = LineControl::new(DataBits::Eight, Parity::None, StopBits::One);
+
+    const BUFFER_SIZE: usize = 256;
+
+    /* Line status bits */
+    const DATA_READY: u8 = 0x01;
+    const TRANSMIT_EMPTY: u8 = 0x20;
+
+    /* Interrupt enable bits */
+    const RX_INTERRUPT: u8 = 0x01;
+    const TX_INTERRUPT: u8 = 0x02;
+
+    /****************************************************************/
+    //                            Types                             //
+    /****************************************************************/
+
+    #[allow(dead_code)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[repr(u16)]
+    pub enum Register {
+        Data,
+        InterruptEnable,
+        FifoControl, //< Same register for FIFO control(write) and interrupt identification(read)
+        LineControl,
+        ModemControl,
+        LineStatus,
+        ModemStatus,
+        Scratch
+    }
+
+    #[allow(dead_code)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[repr(u8)]
+    pub enum DataBits {
+        Five,
+        Six,
+        Seven,
+        Eight
+    }
+
+    #[allow(dead_code)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[repr(u8)]
+    pub enum Parity {
+        None  = 0x00,
+        Odd   = 0x08,
+        Even  = 0x18,
+        Mark  = 0x28,
+        Space = 0x38
+    }
+
+    #[allow(dead_code)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[repr(u8)]
+    pub enum StopBits {
+        One = 0x00,
+        Two = 0x04
+    }
+
+    /* Value of the line control register */
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[repr(transparent)]
+    pub struct LineControl(u8);
+
+    impl LineControl {
+        pub const fn new(data: DataBits, parity: Parity, stop: StopBits) -> Self {
+            LineControl(data as u8 | parity as u8 | stop as u8)
+        }
+    }
+
+    #[allow(dead_code)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[repr(u8)]
+    pub enum InitError {
+        InvalidBaud, //< Baud is zero or is not a divisor of `MAX_BAUD`
+        Faulty       //< Loopback test failed; there is probably no UART at this port
+    }
+
+    struct Ring {
+        data: [u8; BUFFER_SIZE],
+        head: usize,
+        len: usize
+    }
+
+    impl Ring {
+        const fn new() -> Self {
+            Ring {
+                data: [0; BUFFER_SIZE],
+                head: 0,
+                len: 0
+            }
+        }
+
+        fn push(&mut self, byte: u8) -> bool {
+            if self.len == BUFFER_SIZE { return false }
+            self.data[(self.head + self.len) % BUFFER_SIZE] = byte;
+            self.len += 1;
+            true
+        }
+
+        fn pop(&mut self) -> Option <u8> {
+            if self.len == 0 { return None }
+            let byte = self.data[self.head];
+            self.head = (self.head + 1) % BUFFER_SIZE;
+            self.len -= 1;
+            Some(byte)
+        }
+    }
+
+    /* 16550 UART */
+    pub struct Uart {
+        base: u16,
+        present: bool,
+        interrupts: bool,
+        rx: Ring,
+        tx: Ring
+    }
+
+    impl Uart {
+        pub const fn new(base: u16) -> Self {
+            Uart {
+                base,
+                present: false,
+                interrupts: false,
+                rx: Ring::new(),
+                tx: Ring::new()
+            }
+        }
+
+        pub unsafe fn read(&self, reg: Register) -> u8 {
+            Port::new(self.base + reg as u16).read()
+        }
+
+        pub unsafe fn write(&self, reg: Register, data: u8) {
+            Port::new(self.base + reg as u16).write(data)
+        }
+
+        pub fn init(&mut self, baud: u32, line: LineControl) -> Result <(), InitError> {
+            if baud == 0 || MAX_BAUD % baud != 0 { return Err(InitError::InvalidBaud) }
+            let divisor = (MAX_BAUD / baud) as u16;
+            self.present = false;
+            unsafe {
+                self.write(Register::InterruptEnable, 0);
+
+                /* DLAB on: data and interrupt enable registers become divisor latch */
+                self.write(Register::LineControl, 0x80);
+                self.write(Register::Data, divisor as u8);
+                self.write(Register::InterruptEnable, (divisor >> 8) as u8);
+                self.write(Register::LineControl, line.0);
+
+                /* Enable and clear FIFOs, 14-byte threshold */
+                self.write(Register::FifoControl, 0xC7);
+
+                /* Loopback mode to check that the chip works */
+                self.write(Register::ModemControl, 0x1E);
+                self.write(Register::Data, 0xAE);
+                if self.read(Register::Data) != 0xAE { return Err(InitError::Faulty) }
+
+                /* Normal mode: DTR, RTS, OUT1 and OUT2(IRQ line) */
+                self.write(Register::ModemControl, 0x0F);
+            }
+            self.present = true;
+            self.interrupts = false;
+            Ok(())
+        }
+
+        pub fn is_present(&self) -> bool {
+            self.present
+        }
+
+        pub fn is_transmit_empty(&self) -> bool {
+            unsafe { self.read(Register::LineStatus) & TRANSMIT_EMPTY != 0 }
+        }
+
+        pub fn has_data(&self) -> bool {
+            unsafe { self.read(Register::LineStatus) & DATA_READY != 0 }
+        }
+
+        /* Sends byte waiting for the transmitter */
+        pub fn send_polled(&mut self, byte: u8) {
+            if !self.present { return }
+            while !self.is_transmit_empty() { core::hint::spin_loop() }
+            unsafe { self.write(Register::Data, byte) }
+        }
+
+        /* Returns received byte, if there is one, without waiting */
+        pub fn receive_polled(&mut self) -> Option <u8> {
+            if self.present && self.has_data() {
+                Some(unsafe { self.read(Register::Data) })
+            } else {
+                None
+            }
+        }
+
+        /* Sends byte; in interrupt mode it is queued and sent from `isr` */
+        pub fn send(&mut self, byte: u8) {
+            if !self.interrupts { return self.send_polled(byte) }
+            while !self.tx.push(byte) {
+                /* Queue is full; drain it by hand */
+                if let Some(byte) = self.tx.pop() { self.send_polled(byte) }
+            }
+            unsafe { self.write(Register::InterruptEnable, RX_INTERRUPT | TX_INTERRUPT) }
+        }
+
+        pub fn receive(&mut self) -> Option <u8> {
+            if self.interrupts { self.rx.pop() } else { self.receive_polled() }
+        }
+
+        /* Switches between polled and interrupt-driven modes */
+        pub fn set_interrupts(&mut self, on: bool) {
+            if !self.present { return }
+            self.interrupts = on;
+            unsafe { self.write(Register::InterruptEnable, if on { RX_INTERRUPT } else { 0 }) }
+            if !on {
+                while let Some(byte) = self.tx.pop() { self.send_polled(byte) }
+            }
+        }
+
+        pub fn isr(&mut self) {
+            loop {
+                let id = unsafe { self.read(Register::FifoControl) } & 0x0F;
+                match id {
+                    /* No pending interrupt */
+                    0x01 => break,
+                    /* Received data available or character timeout */
+                    0x04 | 0x0C => while self.has_data() {
+                        let byte = unsafe { self.read(Register::Data) };
+                        self.rx.push(byte);
+                    },
+                    /* Transmitter holding register empty */
+                    0x02 => match self.tx.pop() {
+                        Some(byte) => unsafe { self.write(Register::Data, byte) },
+                        None => unsafe { self.write(Register::InterruptEnable, RX_INTERRUPT) }
+                    },
+                    /* Line or modem status change, reading the register acknowledges it */
+                    0x06 => { unsafe { self.read(Register::LineStatus); } },
+                    _ => { unsafe { self.read(Register::ModemStatus); } }
+                }
+            }
+        }
+    }
+
+    impl fmt::Write for Uart {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            for byte in s.bytes() {
+                if byte == b'\n' { self.send(b'\r') }
+                self.send(byte)
+            }
+            Ok(())
+        }
+    }
+
+    /****************************************************************/
+    //                           Macros                             //
+    /****************************************************************/
+
+    #[macro_export]
+    macro_rules! serial_print {
+        ($($arg:tt)*) => ($crate::serial::_print(format_args!($($arg)*)));
+    }
+
+    #[macro_export]
+    macro_rules! serial_println {
+        () => ($crate::serial_print!("\n"));
+        ($($arg:tt)*) => ($crate::serial_print!("{}\n", format_args!($($arg)*)));
+    }
+
+    /****************************************************************/
+    //                           Statics                            //
+    /****************************************************************/
+
+    pub static SERIAL1: Mutex <Uart> = Mutex::new(Uart::new(COM1));
+    pub static SERIAL2: Mutex <Uart> = Mutex::new(Uart::new(COM2));
+
+    static MIRROR: AtomicBool = AtomicBool::new(false);
+
+    /****************************************************************/
+    //                     Other functions                          //
+    /****************************************************************/
+
+    pub fn init() {
+        interrupts::without_interrupts(|| {
+            if SERIAL1.lock().init(DEFAULT_BAUD, DEFAULT_LINE).is_ok() { idt::unmask(COM1_IRQ) }
+            if SERIAL2.lock().init(DEFAULT_BAUD, DEFAULT_LINE).is_ok() { idt::unmask(COM2_IRQ) }
+        });
+    }
+
+    /* Whether all `print!` output is also sent to COM1 */
+    pub fn set_mirror(on: bool) {
+        MIRROR.store(on, Ordering::Relaxed)
+    }
+
+    pub fn mirror() -> bool {
+        MIRROR.load(Ordering::Relaxed)
+    }
+
+    pub fn com1_isr() {
+        SERIAL1.lock().isr()
+    }
+
+    pub fn com2_isr() {
+        SERIAL2.lock().isr()
+    }
+
+    #[doc(hidden)]
+    pub fn _print(args: fmt::Arguments) {
+        use fmt::Write;
+
+        interrupts::without_interrupts(|| {
+            SERIAL1.lock().write_fmt(args).unwrap();
+        });
+    }
+
+    /* Drops the output if COM1 is busy */
+    #[doc(hidden)]
+    pub fn _try_print(args: fmt::Arguments) {
+        use fmt::Write;
+
+        interrupts::without_interrupts(|| {
+            if let Some(mut serial) = SERIAL1.try_lock() { serial.write_fmt(args).ok(); }
+        });
+    }
+
+    /* Writes to COM1 even if the port is locked forever, switching it to polled mode. Only for panics */
+    #[doc(hidden)]
+    pub fn _emergency_print(args: fmt::Arguments) {
+        use fmt::Write;
+
+        interrupts::without_interrupts(|| {
+            if SERIAL1.try_lock().is_none() { unsafe { SERIAL1.force_unlock() } }
+            let mut serial = SERIAL1.lock();
+            serial.set_interrupts(false);
+            serial.write_fmt(args).ok();
+        });
+    }
+}
+
+#[cfg(feature = "serial")]
+pub use private::*;
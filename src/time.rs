@@ -1,18 +1,38 @@
 use core::{
     fmt::Debug,
-    convert::From
+    convert::From,
+    ops::{Add, Sub},
+    sync::atomic::{AtomicU64, Ordering}
+};
+use spin::Mutex;
+use x86_64::instructions::{
+    hlt,
+    interrupts,
+    port::Port
 };
-use x86_64::instructions::hlt;
 
 pub const ZERO: Time = Time::new();
 
-static mut TIMER: u64 = 0;
+/* Input clock of the PIT */
+pub const PIT_FREQUENCY: u32 = 1_193_182;
+
+pub const DEFAULT_FREQUENCY: u32 = 1000;
+
+const PIT_CHANNEL0_PORT: u16 = 0x40;
+const PIT_COMMAND_PORT: u16 = 0x43;
+
+/* Channel 0, low then high byte of the divisor, mode 3, binary */
+const CHANNEL0_SQUARE_WAVE: u8 = 0x36;
+
+const MAX_DIVISOR: u32 = 0x10000;
+
+static TIMER: AtomicU64 = AtomicU64::new(0);
 
 pub type Seconds = f32;
 pub type Milliseconds = u32;
 pub type Microseconds = u64;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Time(Microseconds);
 
 impl Time {
@@ -33,6 +53,23 @@ impl Time {
     }
 }
 
+impl Add for Time {
+    type Output = Time;
+
+    fn add(self, other: Time) -> Time {
+        Time(self.0.saturating_add(other.0))
+    }
+}
+
+/* Stops at zero instead of going negative */
+impl Sub for Time {
+    type Output = Time;
+
+    fn sub(self, other: Time) -> Time {
+        Time(self.0.saturating_sub(other.0))
+    }
+}
+
 impl From <Seconds> for Time {
     fn from(seconds: Seconds) -> Self {
         Self::seconds(seconds)
@@ -69,19 +106,96 @@ impl From <Time> for Microseconds {
     }
 }
 
+/* Time at `base_ticks`, so that changing the frequency does not change the past */
+struct Clock {
+    divisor: u32,
+    base_ticks: u64,
+    base: Time
+}
+
+impl Clock {
+    fn elapsed(&self, ticks: u64) -> Time {
+        let since = (ticks - self.base_ticks) as u128 * self.divisor as u128 * 1_000_000 / PIT_FREQUENCY as u128;
+        self.base + Time(since as Microseconds)
+    }
+}
+
+/* The BIOS leaves the largest divisor, about 18.2 Hz */
+static CLOCK: Mutex <Clock> = Mutex::new(Clock {
+    divisor: MAX_DIVISOR,
+    base_ticks: 0,
+    base: ZERO
+});
+
 pub fn timer() -> u64 {
-    unsafe { TIMER }
+    TIMER.load(Ordering::Relaxed)
+}
+
+/* Divisor closest to `frequency` Hz; the counter takes 0 for 65536 */
+const fn divisor(frequency: u32) -> u32 {
+    if frequency == 0 { return MAX_DIVISOR }
+    let divisor = (PIT_FREQUENCY + frequency / 2) / frequency;
+    if divisor < 1 { 1 } else if divisor > MAX_DIVISOR { MAX_DIVISOR } else { divisor }
+}
+
+/* Programs channel 0 to about `frequency` Hz and returns the frequency it really runs at */
+pub fn set_frequency(frequency: u32) -> u32 {
+    let divisor = divisor(frequency);
+    interrupts::without_interrupts(|| {
+        let mut clock = CLOCK.lock();
+        let ticks = timer();
+        clock.base = clock.elapsed(ticks);
+        clock.base_ticks = ticks;
+        clock.divisor = divisor;
+        unsafe {
+            Port::new(PIT_COMMAND_PORT).write(CHANNEL0_SQUARE_WAVE);
+            let mut data = Port::<u8>::new(PIT_CHANNEL0_PORT);
+            data.write(divisor as u8);
+            data.write((divisor >> 8) as u8);
+        }
+    });
+    frequency()
+}
+
+/* Actual frequency of the timer IRQ, rounded to whole Hz */
+pub fn frequency() -> u32 {
+    let divisor = interrupts::without_interrupts(|| CLOCK.lock().divisor);
+    (PIT_FREQUENCY + divisor / 2) / divisor
+}
+
+/* Length of one timer tick */
+pub fn tick() -> Time {
+    ticks_to_time(1)
 }
 
-#[allow(arithmetic_overflow)]
+pub fn ticks_to_time(ticks: u64) -> Time {
+    let divisor = interrupts::without_interrupts(|| CLOCK.lock().divisor);
+    Time((ticks as u128 * divisor as u128 * 1_000_000 / PIT_FREQUENCY as u128) as Microseconds)
+}
+
+/* Ticks that last at least `time` */
+pub fn time_to_ticks(time: Time) -> u64 {
+    let divisor = interrupts::without_interrupts(|| CLOCK.lock().divisor) as u128;
+    let ticks = time.0 as u128 * PIT_FREQUENCY as u128;
+    let tick = divisor * 1_000_000;
+    ((ticks + tick - 1) / tick) as u64
+}
+
+pub fn init() {
+    set_frequency(DEFAULT_FREQUENCY);
+}
+
+/* Time since boot, as precise as a timer tick */
+pub fn now() -> Time {
+    interrupts::without_interrupts(|| CLOCK.lock().elapsed(timer()))
+}
+
+/* Waits `time` rounded up to whole ticks; the first tick may come early, so it may be shorter by less than a tick */
 pub fn sleep(time: Time) {
-    unsafe {
-        let wait = TIMER + (Seconds::from(time) * 18.) as u64;
-        while TIMER < wait { hlt() }
-    }
+    let wait = timer() + time_to_ticks(time);
+    while timer() < wait { hlt() }
 }
 
-#[allow(arithmetic_overflow)]
 pub fn timer_isr() {
-    unsafe { TIMER += 1 }
+    TIMER.fetch_add(1, Ordering::Relaxed);
 }
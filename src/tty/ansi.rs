@@ -0,0 +1,179 @@
+/****************************************************************/
+//                            Uses                              //
+/****************************************************************/
+
+use core::fmt;
+use super::Color;
+
+/****************************************************************/
+//                         Constants                            //
+/****************************************************************/
+
+pub const ESC: u8 = 0x1B;
+
+pub const MAX_PARAMS: usize = 8;
+
+/* Resets all attributes to default */
+pub const RESET: &str = "\x1b[0m";
+
+/****************************************************************/
+//                            Types                             //
+/****************************************************************/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+enum State {
+    Ground,
+    Escape, //< `ESC` was read
+    Csi     //< `ESC [` was read
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Params {
+    values: [u16; MAX_PARAMS],
+    count: u8
+}
+
+impl Params {
+    const fn new() -> Self {
+        Params {
+            values: [0; MAX_PARAMS],
+            count: 0
+        }
+    }
+
+    pub fn as_slice(&self) -> &[u16] {
+        &self.values[..self.count as usize]
+    }
+
+    /* Returns `n`-th parameter or `default` if it is absent or zero */
+    pub fn get(&self, n: usize, default: u16) -> u16 {
+        match self.as_slice().get(n) {
+            Some(&x) if x != 0 => x,
+            _ => default
+        }
+    }
+}
+
+/* What the tty has to do after feeding a byte to the parser */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Print(u8),                //< Ordinary byte, not a part of a sequence
+    Sgr(Params),              //< `ESC [ n;.. m`
+    CursorPosition(u16, u16), //< `ESC [ row;col H`, 1-based
+    CursorUp(u16),            //< `ESC [ n A`
+    CursorDown(u16),          //< `ESC [ n B`
+    CursorForward(u16),       //< `ESC [ n C`
+    CursorBack(u16),          //< `ESC [ n D`
+    EraseDisplay(u16),        //< `ESC [ n J`
+    EraseLine(u16),           //< `ESC [ n K`
+    SaveCursor,               //< `ESC 7` or `ESC [ s`
+    RestoreCursor             //< `ESC 8` or `ESC [ u`
+}
+
+/* VT100/ANSI escape sequences parser */
+#[derive(Debug, Clone, Copy)]
+pub struct Parser {
+    state: State,
+    params: Params
+}
+
+impl Parser {
+    pub const fn new() -> Self {
+        Parser {
+            state: State::Ground,
+            params: Params::new()
+        }
+    }
+
+    /* Drops partially read sequence */
+    pub fn reset(&mut self) {
+        self.state = State::Ground;
+    }
+
+    pub fn feed(&mut self, byte: u8) -> Option <Action> {
+        match self.state {
+            State::Ground => {
+                if byte == ESC {
+                    self.state = State::Escape;
+                    None
+                } else {
+                    Some(Action::Print(byte))
+                }
+            },
+            State::Escape => {
+                self.state = State::Ground;
+                match byte {
+                    b'[' => {
+                        self.state = State::Csi;
+                        self.params = Params::new();
+                        None
+                    },
+                    b'7' => Some(Action::SaveCursor),
+                    b'8' => Some(Action::RestoreCursor),
+                    _ => None
+                }
+            },
+            State::Csi => match byte {
+                b'0'..=b'9' => {
+                    if self.params.count == 0 { self.params.count = 1 }
+                    let value = &mut self.params.values[self.params.count as usize - 1];
+                    *value = value.saturating_mul(10).saturating_add((byte - b'0') as u16);
+                    None
+                },
+                b';' => {
+                    if self.params.count == 0 { self.params.count = 1 }
+                    if (self.params.count as usize) < MAX_PARAMS { self.params.count += 1 }
+                    None
+                },
+                /* Private markers like `?` are accepted and ignored */
+                0x3C..=0x3F | 0x20..=0x2F => None,
+                0x40..=0x7E => {
+                    self.state = State::Ground;
+                    self.dispatch(byte)
+                },
+                _ => {
+                    self.state = State::Ground;
+                    None
+                }
+            }
+        }
+    }
+
+    fn dispatch(&self, fin: u8) -> Option <Action> {
+        let p = &self.params;
+        match fin {
+            b'm' => Some(Action::Sgr(*p)),
+            b'H' | b'f' => Some(Action::CursorPosition(p.get(0, 1), p.get(1, 1))),
+            b'A' => Some(Action::CursorUp(p.get(0, 1))),
+            b'B' => Some(Action::CursorDown(p.get(0, 1))),
+            b'C' => Some(Action::CursorForward(p.get(0, 1))),
+            b'D' => Some(Action::CursorBack(p.get(0, 1))),
+            b'J' => Some(Action::EraseDisplay(p.get(0, 0))),
+            b'K' => Some(Action::EraseLine(p.get(0, 0))),
+            b's' => Some(Action::SaveCursor),
+            b'u' => Some(Action::RestoreCursor),
+            _ => None
+        }
+    }
+}
+
+/* Escape sequence that switches color; `Display`s as `ESC [ fore;back m` */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sgr(pub Color, pub Color);
+
+impl fmt::Display for Sgr {
+    fn fmt(&self, f: &mut fmt::Formatter <'_>) -> fmt::Result {
+        let fore = match self.0.to_ansi() {
+            Some((n, false)) => 30 + n,
+            Some((n, true)) => 90 + n,
+            None => 39
+        };
+        let back = match self.1.to_ansi() {
+            Some((n, false)) => 40 + n,
+            Some((n, true)) => 100 + n,
+            None => 49
+        };
+        write!(f, "\x1b[{};{}m", fore, back)
+    }
+}
@@ -0,0 +1,57 @@
+/****************************************************************/
+//                         Constants                            //
+/****************************************************************/
+
+/* Glyph shown for characters that have no CP437 equivalent(■) */
+pub const REPLACEMENT: u8 = 0xFE;
+
+/* Glyphs of CP437 bytes 0x00..0x20 */
+const LOW: [char; 32] = [
+    '\0', '☺', '☻', '♥', '♦', '♣', '♠', '•', '◘', '○', '◙', '♂', '♀', '♪', '♫', '☼',
+    '►',  '◄', '↕', '‼', '¶', '§', '▬', '↨', '↑', '↓', '→', '←', '∟', '↔', '▲', '▼'
+];
+
+/* Glyphs of CP437 bytes 0x80..=0xFF */
+const HIGH: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å',
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ',
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»',
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐',
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧',
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀',
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩',
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{A0}'
+];
+
+/* Characters that look the same as some CP437 glyph */
+const ALIASES: [(char, u8); 6] = [
+    ('β', 0xE1), //< Greek beta looks like sharp s
+    ('μ', 0xE6), //< Greek mu and micro sign
+    ('\u{2126}', 0xEA), //< Ohm sign, CP437 has Greek omega
+    ('∅', 0xED),
+    ('ϕ', 0xED),
+    ('∈', 0xEE)
+];
+
+/****************************************************************/
+//                     Other functions                          //
+/****************************************************************/
+
+/* Returns CP437 byte showing `c` or `None` if there is no such glyph */
+pub fn encode(c: char) -> Option <u8> {
+    if c.is_ascii() && !c.is_ascii_control() { return Some(c as u8) }
+    if c == '⌂' { return Some(0x7F) }
+    if let Some(index) = HIGH.iter().position(|&x| x == c) { return Some(0x80 + index as u8) }
+    if let Some(index) = LOW.iter().skip(1).position(|&x| x == c) { return Some(1 + index as u8) }
+    ALIASES.iter().find(|&&(x, _)| x == c).map(|&(_, byte)| byte)
+}
+
+/* Returns character shown by CP437 byte `byte` */
+pub fn decode(byte: u8) -> char {
+    match byte {
+        0x00..=0x1F => LOW[byte as usize],
+        0x7F => '⌂',
+        0x80..=0xFF => HIGH[byte as usize - 0x80],
+        _ => byte as char
+    }
+}
@@ -0,0 +1,159 @@
+/****************************************************************/
+//                            Uses                              //
+/****************************************************************/
+
+use core::{
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering}
+};
+use spin::MutexGuard;
+use x86_64::instructions::interrupts;
+use super::{Consoles, Color, VGA, ScreenChar, TTY, buffer_address};
+
+/****************************************************************/
+//                         Constants                            //
+/****************************************************************/
+
+/* How long to wait for the holder of the console lock before breaking it */
+const LOCK_ATTEMPTS: usize = 100_000;
+
+const COLOR: VGA = VGA::make(Color::LightRed, Color::Black);
+
+/****************************************************************/
+//                            Types                             //
+/****************************************************************/
+
+/* Writes straight to VGA memory with a cursor of its own, so neither the allocator nor the consoles are used */
+pub struct Writer;
+
+impl Writer {
+    fn newline(&mut self) {
+        let (width, height) = (WIDTH.load(Ordering::Relaxed), HEIGHT.load(Ordering::Relaxed));
+        X.store(0, Ordering::Relaxed);
+        let y = Y.load(Ordering::Relaxed) + 1;
+        if y < height {
+            Y.store(y, Ordering::Relaxed);
+            return
+        }
+        let buffer = buffer_address() as *mut ScreenChar;
+        unsafe {
+            for pos in 0..width * (height - 1) {
+                buffer.add(pos).write_volatile(buffer.add(pos + width).read_volatile());
+            }
+            for pos in width * (height - 1)..width * height {
+                buffer.add(pos).write_volatile(ScreenChar { ascii: b' ', color: COLOR });
+            }
+        }
+        Y.store(height - 1, Ordering::Relaxed);
+    }
+
+    fn put(&mut self, ascii: u8) {
+        let width = WIDTH.load(Ordering::Relaxed);
+        let (x, y) = (X.load(Ordering::Relaxed), Y.load(Ordering::Relaxed));
+        let buffer = buffer_address() as *mut ScreenChar;
+        unsafe { buffer.add(y * width + x).write_volatile(ScreenChar { ascii, color: COLOR }) }
+        if x + 1 < width { X.store(x + 1, Ordering::Relaxed) } else { self.newline() }
+    }
+}
+
+impl fmt::Write for Writer {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            match c {
+                '\n' => self.newline(),
+                '\r' => X.store(0, Ordering::Relaxed),
+                _ => self.put(super::cp437::encode(c).unwrap_or(super::cp437::REPLACEMENT))
+            }
+        }
+        Ok(())
+    }
+}
+
+/****************************************************************/
+//                           Macros                             //
+/****************************************************************/
+
+#[macro_export]
+macro_rules! emergency_print {
+    ($($arg:tt)*) => ($crate::tty::emergency::_print(format_args!($($arg)*)));
+}
+
+#[macro_export]
+macro_rules! emergency_println {
+    () => ($crate::emergency_print!("\n"));
+    ($($arg:tt)*) => ($crate::emergency_print!("{}\n", format_args!($($arg)*)));
+}
+
+/****************************************************************/
+//                           Statics                            //
+/****************************************************************/
+
+static DEPTH: AtomicUsize = AtomicUsize::new(0);
+
+/* Cursor of `Writer` and the geometry it was set up for by `prepare` */
+static X: AtomicUsize = AtomicUsize::new(0);
+static Y: AtomicUsize = AtomicUsize::new(0);
+static WIDTH: AtomicUsize = AtomicUsize::new(80);
+static HEIGHT: AtomicUsize = AtomicUsize::new(25);
+
+/****************************************************************/
+//                     Other functions                          //
+/****************************************************************/
+
+/* Takes the console lock, breaking it if it is not released in time.
+   The holder is never resumed after a panic, so its broken state does not matter */
+fn force_consoles() -> MutexGuard <'static, Consoles> {
+    for _ in 0..LOCK_ATTEMPTS {
+        if let Some(guard) = TTY.try_lock() { return super::start(guard) }
+        core::hint::spin_loop();
+    }
+    unsafe { TTY.force_unlock() }
+    super::start(TTY.lock())
+}
+
+/* Marks the beginning of a panic, returns how many panics are in progress including this one */
+pub fn enter() -> usize {
+    DEPTH.fetch_add(1, Ordering::SeqCst) + 1
+}
+
+pub fn is_panicking() -> bool {
+    DEPTH.load(Ordering::SeqCst) != 0
+}
+
+/* Takes the geometry from the consoles once and puts the cursor under the last line of the shown console */
+pub fn prepare() {
+    interrupts::without_interrupts(|| {
+        let consoles = force_consoles();
+        let mode = consoles.mode;
+        let tty = &consoles.list[consoles.active];
+        let row = tty.origin as usize / tty.width as usize + tty.y as usize + (tty.x != 0) as usize;
+        WIDTH.store(mode.width() as usize, Ordering::Relaxed);
+        HEIGHT.store(mode.height() as usize, Ordering::Relaxed);
+        X.store(0, Ordering::Relaxed);
+        Y.store(row.min(mode.height() as usize - 1), Ordering::Relaxed);
+        if row >= mode.height() as usize { Writer.newline() }
+    });
+}
+
+/* Writes `s` at the top left corner of the VGA buffer, touching neither consoles nor locks.
+   Used when the consoles themselves may be broken, e.g. by a panic inside the tty */
+pub fn write_vga(s: &str) {
+    let color = VGA::make(Color::White, Color::Red);
+    let buffer = buffer_address() as *mut ScreenChar;
+    for (i, c) in s.chars().enumerate() {
+        let ascii = super::cp437::encode(c).unwrap_or(super::cp437::REPLACEMENT);
+        unsafe { buffer.add(i).write_volatile(ScreenChar { ascii, color }) }
+    }
+}
+
+#[doc(hidden)]
+pub fn _print(args: fmt::Arguments) {
+    use fmt::Write;
+
+    interrupts::without_interrupts(|| {
+        Writer.write_fmt(args).ok();
+    });
+
+    #[cfg(feature = "serial")]
+    crate::serial::_emergency_print(args);
+}
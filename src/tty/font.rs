@@ -0,0 +1,226 @@
+#[cfg(feature = "allocator")]
+mod private {
+    /****************************************************************/
+    //                            Uses                              //
+    /****************************************************************/
+
+    use alloc::vec::Vec;
+    use core::{
+        str,
+        sync::atomic::{AtomicU8, Ordering}
+    };
+    use spin::Mutex;
+    use x86_64::instructions::interrupts;
+    use crate::tty::{
+        self,
+        cp437,
+        vga::{self, GLYPHS}
+    };
+
+    /****************************************************************/
+    //                         Constants                            //
+    /****************************************************************/
+
+    pub const PSF1_MAGIC: [u8; 2] = [0x36, 0x04];
+    pub const PSF2_MAGIC: [u8; 4] = [0x72, 0xB5, 0x4A, 0x86];
+
+    const PSF1_MODE512: u8 = 0x01;
+    const PSF1_MODEHASTAB: u8 = 0x02;
+    const PSF1_MODESEQ: u8 = 0x04;
+
+    const PSF2_HAS_UNICODE_TABLE: u32 = 0x01;
+
+    /****************************************************************/
+    //                            Types                             //
+    /****************************************************************/
+
+    #[allow(dead_code)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[repr(u8)]
+    pub enum FontError {
+        UnknownFormat,  //< Neither PSF1 nor PSF2
+        Truncated,      //< Data is shorter than the header says
+        UnsupportedSize //< Glyphs do not fit the current text mode
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    enum Unicode <'a> {
+        Psf1(&'a [u8]),
+        Psf2(&'a [u8])
+    }
+
+    /* PC Screen Font, borrowed from `include_bytes!` or a buffer read from disk */
+    #[derive(Debug, Clone, Copy)]
+    pub struct Font <'a> {
+        glyphs: &'a [u8],
+        count: usize,
+        width: u32,
+        height: u8,
+        glyph_size: usize,
+        unicode: Option <Unicode <'a>>
+    }
+
+    impl <'a> Font <'a> {
+        pub fn parse(data: &'a [u8]) -> Result <Font <'a>, FontError> {
+            if data.starts_with(&PSF2_MAGIC) {
+                Self::parse_psf2(data)
+            } else if data.starts_with(&PSF1_MAGIC) {
+                Self::parse_psf1(data)
+            } else {
+                Err(FontError::UnknownFormat)
+            }
+        }
+
+        fn parse_psf1(data: &'a [u8]) -> Result <Font <'a>, FontError> {
+            if data.len() < 4 { return Err(FontError::Truncated) }
+            let mode = data[2];
+            let height = data[3];
+            let count = if mode & PSF1_MODE512 != 0 { 512 } else { 256 };
+            let end = 4 + count * height as usize;
+            if data.len() < end { return Err(FontError::Truncated) }
+            Ok(Font {
+                glyphs: &data[4..end],
+                count,
+                width: 8,
+                height,
+                glyph_size: height as usize,
+                unicode: if mode & (PSF1_MODEHASTAB | PSF1_MODESEQ) != 0 { Some(Unicode::Psf1(&data[end..])) } else { None }
+            })
+        }
+
+        fn parse_psf2(data: &'a [u8]) -> Result <Font <'a>, FontError> {
+            let field = |n: usize| -> Result <u32, FontError> {
+                let bytes = data.get(4 + n * 4..8 + n * 4).ok_or(FontError::Truncated)?;
+                Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            };
+            let header = field(1)? as usize;
+            let flags = field(2)?;
+            let count = field(3)? as usize;
+            let glyph_size = field(4)? as usize;
+            let height = field(5)?;
+            let width = field(6)?;
+            if height > u8::MAX as u32 { return Err(FontError::UnsupportedSize) }
+            let end = header + count * glyph_size;
+            if data.len() < end { return Err(FontError::Truncated) }
+            Ok(Font {
+                glyphs: &data[header..end],
+                count,
+                width,
+                height: height as u8,
+                glyph_size,
+                unicode: if flags & PSF2_HAS_UNICODE_TABLE != 0 { Some(Unicode::Psf2(&data[end..])) } else { None }
+            })
+        }
+
+        pub fn height(&self) -> u8 {
+            self.height
+        }
+
+        pub fn width(&self) -> u32 {
+            self.width
+        }
+
+        pub fn count(&self) -> usize {
+            self.count
+        }
+
+        pub fn glyph(&self, index: usize) -> Option <&'a [u8]> {
+            if index >= self.count { return None }
+            Some(&self.glyphs[index * self.glyph_size..(index + 1) * self.glyph_size])
+        }
+
+        /* Returns sorted character -> glyph table for the first `GLYPHS` glyphs, empty if the font has no unicode table */
+        pub fn map(&self) -> Vec <(char, u8)> {
+            let mut map = Vec::new();
+            match self.unicode {
+                None => { },
+                Some(Unicode::Psf1(table)) => {
+                    let mut glyph = 0;
+                    let mut sequence = false;
+                    for entry in table.chunks_exact(2) {
+                        if glyph >= GLYPHS { break }
+                        match u16::from_le_bytes([entry[0], entry[1]]) {
+                            0xFFFF => {
+                                glyph += 1;
+                                sequence = false;
+                            },
+                            /* Sequences of several characters are not supported */
+                            0xFFFE => sequence = true,
+                            x => if !sequence {
+                                if let Some(c) = char::from_u32(x as u32) { map.push((c, glyph as u8)) }
+                            }
+                        }
+                    }
+                },
+                Some(Unicode::Psf2(table)) => {
+                    for (glyph, entry) in table.split(|&b| b == 0xFF).take(GLYPHS.min(self.count)).enumerate() {
+                        let single = entry.split(|&b| b == 0xFE).next().unwrap_or(&[]);
+                        if let Ok(s) = str::from_utf8(single) {
+                            for c in s.chars() { map.push((c, glyph as u8)) }
+                        }
+                    }
+                }
+            }
+            map.sort_unstable_by_key(|&(c, _)| c);
+            map.dedup_by_key(|&mut (c, _)| c);
+            map
+        }
+    }
+
+    /****************************************************************/
+    //                           Statics                            //
+    /****************************************************************/
+
+    /* Character -> glyph table of the loaded font; empty means the font is CP437 */
+    static MAP: Mutex <Vec <(char, u8)>> = Mutex::new(Vec::new());
+
+    static REPLACEMENT: AtomicU8 = AtomicU8::new(cp437::REPLACEMENT);
+
+    /****************************************************************/
+    //                     Other functions                          //
+    /****************************************************************/
+
+    /* Copies glyphs into VGA plane 2, which holds the character generator RAM */
+    unsafe fn write_glyphs(font: &Font) {
+        vga::map_font_memory();
+        for index in 0..GLYPHS.min(font.count) {
+            vga::write_glyph(index, font.glyph(index).unwrap());
+        }
+        vga::unmap_font_memory();
+    }
+
+    /* Loads `font` into the VGA character generator and uses its unicode table for output.
+       Glyph height must match `tty::mode().char_height()` */
+    pub fn load(font: &Font) -> Result <(), FontError> {
+        if font.width != 8 || font.height != tty::mode().char_height() { return Err(FontError::UnsupportedSize) }
+        let map = font.map();
+        let replacement = ['\u{FFFD}', '?'].iter().find_map(|&r| map.binary_search_by_key(&r, |&(c, _)| c).ok().map(|i| map[i].1));
+        interrupts::without_interrupts(|| {
+            unsafe { write_glyphs(font) };
+            REPLACEMENT.store(replacement.unwrap_or(cp437::REPLACEMENT), Ordering::Relaxed);
+            *MAP.lock() = map;
+        });
+        Ok(())
+    }
+
+    /* Returns glyph of `c` in the loaded font or `None` if the loaded font is CP437 */
+    pub fn encode(c: char) -> Option <u8> {
+        let map = MAP.lock();
+        if map.is_empty() { return None }
+        Some(match map.binary_search_by_key(&c, |&(c, _)| c) {
+            Ok(index) => map[index].1,
+            Err(_) => REPLACEMENT.load(Ordering::Relaxed)
+        })
+    }
+
+    /* Reads `sectors` sectors starting from `lba` of ATA drive `drive`, the result can be passed to `Font::parse` */
+    #[cfg(all(feature = "hdd", feature = "enum", feature = "time"))]
+    pub unsafe fn read(drive: u8, lba: u32, sectors: u8) -> Result <Vec <u8>, crate::hdd::PollingError> {
+        let buffer = alloc::vec![0u8; sectors as usize * 512];
+        crate::hdd::ata_access(crate::hdd::Direction::Read, drive, lba, sectors, buffer.as_slice())?;
+        Ok(buffer)
+    }
+}
+
+#[cfg(feature = "allocator")]
+pub use private::*;
@@ -0,0 +1,232 @@
+#[cfg(all(feature = "keyboard", feature = "allocator"))]
+mod private {
+    /****************************************************************/
+    //                            Uses                              //
+    /****************************************************************/
+
+    use alloc::string::String;
+    use spin::Mutex;
+    use x86_64::instructions::interrupts;
+    use crate::{
+        print,
+        keyboard::{self, keymap, KeyEvent, Propagation}
+    };
+
+    /****************************************************************/
+    //                         Constants                            //
+    /****************************************************************/
+
+    pub const ERASE: char = '\x08';      //< Backspace
+    pub const WORD_ERASE: char = '\x17'; //< Ctrl+W
+    pub const KILL: char = '\x15';       //< Ctrl+U
+    pub const INTR: char = '\x03';       //< Ctrl+C
+    pub const EOL: char = '\n';
+
+    /* Characters typed beyond this are dropped until someone reads */
+    pub const MAX_INPUT: usize = 256;
+
+    /****************************************************************/
+    //                            Types                             //
+    /****************************************************************/
+
+    /* Settings of the line discipline, like termios of Unix */
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Termios {
+        pub canonical: bool, //< Input becomes readable line by line and can be edited before Enter
+        pub echo: bool,
+        pub erase: char,
+        pub word_erase: char,
+        pub kill: char,
+        pub intr: char, //< Drops the line being edited, the signal itself is `keyboard::interrupted`
+        pub eol: char
+    }
+
+    impl Termios {
+        pub const fn new() -> Self {
+            Termios {
+                canonical: true,
+                echo: true,
+                erase: ERASE,
+                word_erase: WORD_ERASE,
+                kill: KILL,
+                intr: INTR,
+                eol: EOL
+            }
+        }
+    }
+
+    struct Discipline {
+        termios: Termios,
+        line: String, //< Line being edited in canonical mode
+        input: String //< Characters ready to be read
+    }
+
+    impl Discipline {
+        const fn new() -> Self {
+            Discipline {
+                termios: Termios::new(),
+                line: String::new(),
+                input: String::new()
+            }
+        }
+
+        fn echo(&self, c: char) {
+            if !self.termios.echo { return }
+            if c.is_control() && c != '\n' && c != '\t' {
+                print!("^{}", ((c as u8) | 0x40) as char);
+            } else {
+                print!("{}", c);
+            }
+        }
+
+        /* Erases last character of the line from the screen too */
+        fn erase(&mut self) -> bool {
+            match self.line.pop() {
+                None => false,
+                Some(c) => {
+                    if self.termios.echo {
+                        for _ in 0..width(c) { print!("\x08") }
+                    }
+                    true
+                }
+            }
+        }
+
+        fn receive(&mut self, c: char) {
+            if self.input.chars().count() + self.line.chars().count() >= MAX_INPUT && c != self.termios.eol { return }
+            if !self.termios.canonical {
+                self.input.push(c);
+                return self.echo(c)
+            }
+            let termios = self.termios;
+            if c == termios.erase {
+                self.erase();
+            } else if c == termios.kill {
+                while self.erase() { }
+            } else if c == termios.intr {
+                self.echo(c);
+                self.echo('\n');
+                self.line.clear();
+            } else if c == termios.word_erase {
+                while self.line.ends_with(' ') { self.erase(); }
+                while !self.line.is_empty() && !self.line.ends_with(' ') { self.erase(); }
+            } else if c == termios.eol {
+                self.echo(c);
+                let line = core::mem::take(&mut self.line);
+                self.input.push_str(&line);
+                self.input.push(c);
+            } else {
+                self.echo(c);
+                self.line.push(c);
+            }
+        }
+    }
+
+    /****************************************************************/
+    //                           Statics                            //
+    /****************************************************************/
+
+    static LDISC: Mutex <Discipline> = Mutex::new(Discipline::new());
+
+    /****************************************************************/
+    //                     Other functions                          //
+    /****************************************************************/
+
+    /* How many columns the echo of `c` takes */
+    fn width(c: char) -> usize {
+        match c {
+            '\t' => 4,
+            c if c.is_control() => 2,
+            _ => 1
+        }
+    }
+
+    /* Feeds typed characters to the line discipline, registered by `keyboard::init` */
+    pub fn keyboard_handler(event: KeyEvent) -> Propagation {
+        if event.is_release() { return Propagation::Continue }
+        /* Control characters come from the letter positions of the US layout, whatever layout is active */
+        if event.modifiers.ctrl() {
+            if let Some(c) = keymap::US.translate(event.key, false).filter(char::is_ascii_alphabetic) {
+                receive(((c.to_ascii_uppercase() as u8) & 0x1F) as char);
+                return Propagation::Stop
+            }
+        }
+        match event.as_char().and_then(keymap::compose) {
+            Some(c) => {
+                receive(c);
+                Propagation::Stop
+            },
+            None => Propagation::Continue
+        }
+    }
+
+    pub fn receive(c: char) {
+        interrupts::without_interrupts(|| LDISC.lock().receive(c))
+    }
+
+    pub fn termios() -> Termios {
+        interrupts::without_interrupts(|| LDISC.lock().termios)
+    }
+
+    /* Switching to raw mode makes the line typed so far readable */
+    pub fn set_termios(termios: Termios) {
+        interrupts::without_interrupts(|| {
+            let mut ldisc = LDISC.lock();
+            if !termios.canonical {
+                let line = core::mem::take(&mut ldisc.line);
+                ldisc.input.push_str(&line);
+            }
+            ldisc.termios = termios;
+        })
+    }
+
+    pub fn set_canonical(on: bool) {
+        set_termios(Termios { canonical: on, ..termios() })
+    }
+
+    pub fn set_echo(on: bool) {
+        set_termios(Termios { echo: on, ..termios() })
+    }
+
+    /* Drops all the unread input */
+    pub fn flush() {
+        interrupts::without_interrupts(|| {
+            let mut ldisc = LDISC.lock();
+            ldisc.line.clear();
+            ldisc.input.clear();
+        })
+    }
+
+    pub fn try_read_char() -> Option <char> {
+        interrupts::without_interrupts(|| {
+            let mut ldisc = LDISC.lock();
+            if ldisc.input.is_empty() { None } else { Some(ldisc.input.remove(0)) }
+        })
+    }
+
+    /* Waits for a character; in canonical mode characters appear only after the whole line is typed.
+       Key events are taken from the keyboard queue meanwhile, which runs the handlers including ours */
+    pub fn read_char() -> char {
+        loop {
+            if let Some(c) = try_read_char() { return c }
+            keyboard::next_event();
+        }
+    }
+
+    /* Reads characters into `s` up to and including `delim` */
+    pub fn read_until(s: &mut String, delim: char) {
+        s.clear();
+        loop {
+            let c = read_char();
+            s.push(c);
+            if c == delim { break }
+        }
+    }
+
+    pub fn read_line(s: &mut String) {
+        read_until(s, termios().eol)
+    }
+}
+
+#[cfg(all(feature = "keyboard", feature = "allocator"))]
+pub use private::*;
@@ -0,0 +1,871 @@
+/****************************************************************/
+//                          Modules                             //
+/****************************************************************/
+
+pub mod ansi;
+
+pub mod cp437;
+
+pub mod emergency;
+
+#[cfg(feature = "allocator")]
+pub mod font;
+
+#[cfg(all(feature = "keyboard", feature = "allocator"))]
+pub mod ldisc;
+
+#[cfg(feature = "allocator")]
+mod scrollback;
+
+pub mod pointer;
+
+pub mod status;
+
+pub mod vga;
+
+/****************************************************************/
+//                            Uses                              //
+/****************************************************************/
+
+use core::fmt;
+use volatile::Volatile;
+use spin::{Mutex, MutexGuard};
+use x86_64::instructions::{
+    interrupts,
+    port::Port
+};
+use ansi::{Parser, Action, Params};
+pub use ansi::{Sgr, RESET};
+#[cfg(feature = "allocator")]
+use scrollback::Scrollback;
+#[cfg(feature = "allocator")]
+pub use scrollback::DEFAULT_SCROLLBACK_DEPTH;
+pub use status::StatusPosition;
+pub use pointer::{show_pointer, hide_pointer, pointer_visible, pointer_position, set_pointer_position, move_pointer};
+
+/****************************************************************/
+//                         Constants                            //
+/****************************************************************/
+
+/* Geometry of the biggest supported text mode */
+pub const MAX_WIDTH : u8 = 90;
+pub const MAX_HEIGHT: u8 = 60;
+
+const MAX_CELLS: usize = MAX_WIDTH as usize * MAX_HEIGHT as usize;
+
+pub const DEFAULT_FOREGROUND: Color = Color::Yellow;
+pub const DEFAULT_BACKGROUND: Color = Color::Black;
+
+pub const DEFAULT: VGA = VGA::make(DEFAULT_FOREGROUND, DEFAULT_BACKGROUND);
+
+/* Count of virtual consoles */
+pub const CONSOLES: usize = 6;
+
+pub const BIOS_CONFIG_PORT: u16 = 0x3D4;
+pub const BIOS_DATA_PORT: u16 = 0x3D5;
+
+/* CRTC registers, selected through `BIOS_CONFIG_PORT` */
+pub const CURSOR_START_REGISTER: u8 = 0x0A;
+pub const CURSOR_END_REGISTER: u8 = 0x0B;
+pub const CURSOR_HIGH_REGISTER: u8 = 0x0E;
+pub const CURSOR_LOW_REGISTER: u8 = 0x0F;
+
+/* Bit of the cursor start register that hides the cursor */
+const CURSOR_DISABLE: u8 = 0x20;
+
+/* Physical address, only its first page is identity-mapped so it is reached through `vga::memory` */
+const BUFFER_ADDRESS: usize = 0xB8000;
+
+/****************************************************************/
+//                            Types                             //
+/****************************************************************/
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+
+/* VGA colors */
+pub enum Color {
+    Black,
+    Blue,
+    Green,
+    Cyan,
+    Red,
+    Magenta,
+    Brown,
+    LightGray,
+    DarkGray,
+    LightBlue,
+    LightGreen,
+    LightCyan,
+    LightRed,
+    Pink,
+    Yellow,
+    White,
+
+    Default, //< Use this one to mark default color(fore: Yellow, back: Black)
+
+    Count //< Count of all colors
+}
+
+/* VGA colors in the order of ANSI color indices(0 - black .. 7 - white) */
+const ANSI_COLORS: [Color; 8] = [Color::Black, Color::Red, Color::Green, Color::Brown, Color::Blue, Color::Magenta, Color::Cyan, Color::LightGray];
+const ANSI_BRIGHT_COLORS: [Color; 8] = [Color::DarkGray, Color::LightRed, Color::LightGreen, Color::Yellow, Color::LightBlue, Color::Pink, Color::LightCyan, Color::White];
+
+impl Color {
+    const fn const_eq(self, other: Self) -> bool {
+        self as u8 == other as u8
+    }
+
+    /* Makes VGA color from ANSI color index(0..8) */
+    pub fn from_ansi(index: u8, bright: bool) -> Color {
+        if bright { ANSI_BRIGHT_COLORS[index as usize & 7] } else { ANSI_COLORS[index as usize & 7] }
+    }
+
+    /* Returns ANSI color index and brightness, `None` for `Default` */
+    pub fn to_ansi(self) -> Option <(u8, bool)> {
+        if let Some(index) = ANSI_COLORS.iter().position(|&c| c == self) {
+            Some((index as u8, false))
+        } else {
+            ANSI_BRIGHT_COLORS.iter().position(|&c| c == self).map(|index| (index as u8, true))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(transparent)]
+
+/* Represents VGA color */
+pub struct VGA(u8);
+
+impl VGA {
+    /* Makes VGA color from foreground and background */
+    pub const fn make(foreground: Color, background: Color) -> VGA {
+        VGA((if background.const_eq(Color::Default) { (DEFAULT_BACKGROUND as u8) << 4 } else { (background as u8) << 4 }) | (if foreground.const_eq(Color::Default) { DEFAULT_FOREGROUND as u8 } else { foreground as u8 }))
+    }
+
+    pub const fn fore(self) -> Color {
+        let fore = self.0 & 0x0F;
+        unsafe { *(&fore as *const u8 as *const Color) }
+    }
+
+    pub const fn back(self) -> Color {
+        let back = self.0 >> 4;
+        unsafe { *(&back as *const u8 as *const Color) }
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+
+/* Shape of the hardware cursor */
+pub enum CursorShape {
+    Underline,
+    Block,
+    Scanlines(u8, u8) //< First and last scanline of the cell, both below Mode::char_height()
+}
+
+impl CursorShape {
+    /* `char_height` is the glyph height, so the last scanline is `char_height - 1` */
+    pub const fn scanlines(self, char_height: u8) -> (u8, u8) {
+        match self {
+            CursorShape::Underline => (char_height - 2, char_height - 1),
+            CursorShape::Block => (0, char_height - 1),
+            CursorShape::Scanlines(start, end) => (start, end)
+        }
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+
+/* Standard VGA text modes */
+pub enum Mode {
+    Text80x25,
+    Text80x50,
+    Text90x30,
+    Text90x60
+}
+
+impl Mode {
+    pub const fn width(self) -> u8 {
+        match self {
+            Mode::Text80x25 | Mode::Text80x50 => 80,
+            Mode::Text90x30 | Mode::Text90x60 => 90
+        }
+    }
+
+    pub const fn height(self) -> u8 {
+        match self {
+            Mode::Text80x25 => 25,
+            Mode::Text80x50 => 50,
+            Mode::Text90x30 => 30,
+            Mode::Text90x60 => 60
+        }
+    }
+
+    /* Scanlines per character */
+    pub const fn char_height(self) -> u8 {
+        match self {
+            Mode::Text80x25 | Mode::Text90x30 => 16,
+            Mode::Text80x50 | Mode::Text90x60 => 8
+        }
+    }
+
+    fn registers(self) -> &'static vga::Registers {
+        match self {
+            Mode::Text80x25 => &vga::TEXT_80X25,
+            Mode::Text80x50 => &vga::TEXT_80X50,
+            Mode::Text90x30 => &vga::TEXT_90X30,
+            Mode::Text90x60 => &vga::TEXT_90X60
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+struct ScreenChar {
+    ascii: u8,
+    color: VGA,
+}
+
+/* VGA memory, `width` cells per row */
+#[repr(transparent)]
+struct Buffer {
+    chars: [Volatile <ScreenChar>; MAX_CELLS]
+}
+
+type Line = [ScreenChar; MAX_WIDTH as usize];
+
+/* Off-screen copy of the console contents, `width` cells per row */
+type Screen = [ScreenChar; MAX_CELLS];
+
+const BLANK: ScreenChar = ScreenChar {
+    ascii: b' ',
+    color: DEFAULT
+};
+
+/* Virtual console */
+struct Static {
+    screen: Screen,
+    active: bool, //< Whether the console is shown on the screen
+    width: u8,
+    height: u8,
+    char_height: u8,
+    x: u8,
+    y: u8,
+    color: VGA,
+    reversed: bool,
+    saved: (u8, u8),
+    parser: Parser,
+    cursor: CursorShape,
+    cursor_visible: bool,
+    origin: u16, //< Cells of VGA memory before the console, taken by the status line
+    #[cfg(feature = "allocator")]
+    history: Scrollback
+}
+
+impl Static {
+    pub const fn new(mode: Mode) -> Static {
+        Static {
+            screen: [BLANK; MAX_CELLS],
+            active: false,
+            width: mode.width(),
+            height: mode.height(),
+            char_height: mode.char_height(),
+            x: 0,
+            y: 0,
+            color: VGA::make(Color::Default, Color::Default),
+            reversed: false,
+            saved: (0, 0),
+            parser: Parser::new(),
+            cursor: CursorShape::Underline,
+            cursor_visible: true,
+            origin: 0,
+            #[cfg(feature = "allocator")]
+            history: Scrollback::new()
+        }
+    }
+
+    /* Shows the console on the screen */
+    fn activate(&mut self) {
+        self.active = true;
+        self.redraw();
+        self.update_cursor_shape();
+        self.update_cursor();
+    }
+
+    fn deactivate(&mut self) {
+        #[cfg(feature = "allocator")]
+        self.view(0);
+        self.active = false;
+    }
+
+    fn is_visible(&self) -> bool {
+        self.active && !self.is_scrolled()
+    }
+
+    /* Whether the view is moved into the scrollback */
+    fn is_scrolled(&self) -> bool {
+        #[cfg(feature = "allocator")]
+        if self.history.is_scrolled() { return true }
+        false
+    }
+
+    fn cells(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+
+    /* Copies the whole console to VGA memory */
+    fn redraw(&self) {
+        if !self.is_visible() { return }
+        let buffer = self.buffer();
+        for (pos, &c) in self.screen[..self.cells()].iter().enumerate() {
+            buffer.chars[pos].write(c);
+        }
+    }
+
+    /* Puts `c` at linear position `pos` */
+    fn put(&mut self, pos: usize, c: ScreenChar) {
+        self.screen[pos] = c;
+        if self.is_visible() {
+            self.buffer().chars[pos].write(c);
+        }
+    }
+
+    /* Changes geometry keeping the lines around the cursor, the status line takes one row of `mode` */
+    fn resize(&mut self, mode: Mode, status: Option <StatusPosition>) {
+        #[cfg(feature = "allocator")]
+        self.view(0);
+        let rows = if status.is_some() { mode.height() - 1 } else { mode.height() };
+        let (width, height) = (mode.width() as usize, rows as usize);
+        let (old_width, old_height) = (self.width as usize, self.height as usize);
+        let skip = (self.y as usize + 1).saturating_sub(height);
+        let old = self.screen;
+        for y in 0..height {
+            for x in 0..width {
+                self.screen[y * width + x] = if x < old_width && y + skip < old_height { old[(y + skip) * old_width + x] } else { BLANK };
+            }
+        }
+        self.width = mode.width();
+        self.height = rows;
+        self.origin = if status == Some(StatusPosition::Top) { mode.width() as u16 } else { 0 };
+        self.char_height = mode.char_height();
+        self.y -= skip as u8;
+        self.x = self.x.min(self.width - 1);
+        self.saved = (self.saved.0.min(self.width - 1), self.saved.1.min(self.height - 1));
+    }
+
+    fn write_crtc(register: u8, value: u8) {
+        unsafe {
+            Port::new(BIOS_CONFIG_PORT).write(register);
+            Port::new(BIOS_DATA_PORT).write(value);
+        }
+    }
+
+    fn update_cursor_shape(&mut self) {
+        if !self.active { return }
+        let (start, end) = self.cursor.scanlines(self.char_height);
+        /* The cursor has no place in the scrollback */
+        let disable = if self.cursor_visible && !self.is_scrolled() { 0 } else { CURSOR_DISABLE };
+        Self::write_crtc(CURSOR_START_REGISTER, (start & 0x1F) | disable);
+        Self::write_crtc(CURSOR_END_REGISTER, end & 0x1F);
+    }
+
+    /* Moves the hardware cursor to (x, y), it stays hidden while the view is scrolled */
+    fn update_cursor(&self) {
+        if !self.active || self.is_scrolled() { return }
+        let position = self.position() as u16 + self.origin;
+        Self::write_crtc(CURSOR_LOW_REGISTER, position as u8);
+        Self::write_crtc(CURSOR_HIGH_REGISTER, (position >> 8) as u8);
+    }
+
+    /* VGA memory of the console, it starts after the status line */
+    fn buffer(&self) -> &'static mut Buffer {
+        unsafe { &mut *((buffer_address() + self.origin as usize * 2) as *mut Buffer) }
+    }
+
+    /* Moves the view `offset` lines up into the scrollback, 0 - live view */
+    #[cfg(feature = "allocator")]
+    fn view(&mut self, offset: usize) {
+        if !self.active || offset == self.history.offset() { return }
+        let buffer = self.buffer();
+        self.history.view(buffer, &self.screen, self.width as usize, self.height as usize, offset);
+        self.update_cursor_shape();
+        self.update_cursor();
+    }
+
+    fn newline(&mut self) {
+        self.y += 1;
+        self.x = 0;
+    }
+
+    fn scroll(&mut self) {
+        let width = self.width as usize;
+        let cells = self.cells();
+        #[cfg(feature = "allocator")]
+        self.history.push(&self.screen[..width]);
+        self.screen.copy_within(width..cells, 0);
+        let c = ScreenChar {
+            ascii: b' ',
+            color: self.color
+        };
+        for cell in self.screen[cells - width..cells].iter_mut() { *cell = c }
+        self.redraw();
+        self.x = 0;
+        self.y -= 1;
+    }
+
+    /* Fills cells in [from, to) (linear positions, row by row) with spaces */
+    fn erase(&mut self, from: usize, to: usize) {
+        let c = ScreenChar {
+            ascii: b' ',
+            color: self.color
+        };
+        for pos in from..to {
+            self.put(pos, c);
+        }
+    }
+
+    fn position(&self) -> usize {
+        self.y as usize * self.width as usize + self.x as usize
+    }
+
+    fn write_byte(&mut self, byte: u8) {
+        match byte {
+            b'\0' => { },
+            b'\n' => self.newline(),
+            b'\t' => {
+                for _ in 0..4 { self.write_byte(b' ') }
+            },
+            b'\x08' => {
+                if self.x == 0 {
+                    if self.y != 0 {
+                        self.y -= 1;
+                        self.x = self.width - 1;
+                    }
+                } else {
+                    self.x -= 1;
+                }
+                self.put(self.position(), ScreenChar {
+                    ascii: b' ',
+                    color: self.color
+                });
+            },
+            byte => self.put_glyph(byte)
+        }
+        self.wrap();
+    }
+
+    fn wrap(&mut self) {
+        if self.x == self.width { self.newline(); }
+        if self.y == self.height { self.scroll(); }
+    }
+
+    /* Puts CP437 glyph at the cursor, control bytes are not interpreted */
+    fn put_glyph(&mut self, byte: u8) {
+        self.put(self.position(), ScreenChar {
+            ascii: byte,
+            color: self.color
+        });
+        self.x += 1;
+        self.wrap();
+    }
+
+    fn write_unicode(&mut self, c: char) {
+        if c.is_ascii() {
+            if let Some(action) = self.parser.feed(c as u8) {
+                self.apply(action)
+            }
+        } else {
+            /* Escape sequences consist of ASCII only */
+            self.parser.reset();
+            #[cfg(feature = "allocator")]
+            let glyph = font::encode(c);
+            #[cfg(not(feature = "allocator"))]
+            let glyph = None;
+            self.put_glyph(glyph.or_else(|| cp437::encode(c)).unwrap_or(cp437::REPLACEMENT));
+        }
+    }
+
+    fn write_raw(&mut self, bytes: &[u8]) {
+        #[cfg(feature = "allocator")]
+        self.view(0);
+        for &byte in bytes { self.put_glyph(byte) }
+        self.update_cursor();
+    }
+
+    fn sgr(&mut self, params: &Params) {
+        /* `ESC [ m` is the same as `ESC [ 0 m` */
+        let params = if params.as_slice().is_empty() { &[0][..] } else { params.as_slice() };
+        for &p in params {
+            let (fore, back) = if self.reversed { (self.color.back(), self.color.fore()) } else { (self.color.fore(), self.color.back()) };
+            let (fore, back) = match p {
+                0 => {
+                    self.reversed = false;
+                    (DEFAULT_FOREGROUND, DEFAULT_BACKGROUND)
+                },
+                1 => (fore.to_ansi().map_or(fore, |(n, _)| Color::from_ansi(n, true)), back),
+                22 => (fore.to_ansi().map_or(fore, |(n, _)| Color::from_ansi(n, false)), back),
+                7 => {
+                    self.reversed = true;
+                    (fore, back)
+                },
+                27 => {
+                    self.reversed = false;
+                    (fore, back)
+                },
+                30..=37 => (Color::from_ansi((p - 30) as u8, false), back),
+                39 => (DEFAULT_FOREGROUND, back),
+                40..=47 => (fore, Color::from_ansi((p - 40) as u8, false)),
+                49 => (fore, DEFAULT_BACKGROUND),
+                90..=97 => (Color::from_ansi((p - 90) as u8, true), back),
+                100..=107 => (fore, Color::from_ansi((p - 100) as u8, true)),
+                _ => (fore, back)
+            };
+            self.color = if self.reversed { VGA::make(back, fore) } else { VGA::make(fore, back) };
+        }
+    }
+
+    fn apply(&mut self, action: Action) {
+        match action {
+            Action::Print(byte) => self.write_byte(byte),
+            Action::Sgr(params) => self.sgr(&params),
+            Action::CursorPosition(row, col) => {
+                self.y = (row.min(self.height as u16) - 1) as u8;
+                self.x = (col.min(self.width as u16) - 1) as u8;
+            },
+            Action::CursorUp(n) => self.y = self.y.saturating_sub(n.min(self.height as u16) as u8),
+            Action::CursorDown(n) => self.y = (self.y as u16 + n).min(self.height as u16 - 1) as u8,
+            Action::CursorForward(n) => self.x = (self.x as u16 + n).min(self.width as u16 - 1) as u8,
+            Action::CursorBack(n) => self.x = self.x.saturating_sub(n.min(self.width as u16) as u8),
+            Action::EraseDisplay(mode) => match mode {
+                0 => self.erase(self.position(), self.cells()),
+                1 => self.erase(0, self.position() + 1),
+                _ => self.erase(0, self.cells())
+            },
+            Action::EraseLine(mode) => {
+                let width = self.width as usize;
+                let line = self.y as usize * width;
+                match mode {
+                    0 => self.erase(self.position(), line + width),
+                    1 => self.erase(line, self.position() + 1),
+                    _ => self.erase(line, line + width)
+                }
+            },
+            Action::SaveCursor => self.saved = (self.x, self.y),
+            Action::RestoreCursor => {
+                self.x = self.saved.0;
+                self.y = self.saved.1;
+            }
+        }
+    }
+}
+
+impl fmt::Write for Static {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        #[cfg(feature = "allocator")]
+        self.view(0);
+        for c in s.chars() { self.write_unicode(c) }
+        self.update_cursor();
+        Ok(())
+    }
+
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        #[cfg(feature = "allocator")]
+        self.view(0);
+        self.write_unicode(c);
+        self.update_cursor();
+        Ok(())
+    }
+}
+
+/****************************************************************/
+//                           Macros                             //
+/****************************************************************/
+
+#[macro_export]
+macro_rules! print {
+    (@ $console:expr, $($arg:tt)*) => ($crate::tty::_print_to($console, format_args!($($arg)*)));
+    ($($arg:tt)*) => ($crate::tty::_print(format_args!($($arg)*)));
+}
+
+#[macro_export]
+macro_rules! println {
+    () => ($crate::print!("\n"));
+    (@ $console:expr, $($arg:tt)*) => ($crate::print!(@ $console, "{}\n", format_args!($($arg)*)));
+    (@ $console:expr) => ($crate::print!(@ $console, "\n"));
+    ($($arg:tt)*) => ($crate::print!("{}\n", format_args!($($arg)*)));
+}
+
+/* Never waits: the output is dropped if the consoles or the serial port are busy. For interrupt handlers */
+#[macro_export]
+macro_rules! try_print {
+    ($($arg:tt)*) => ($crate::tty::_try_print(format_args!($($arg)*)));
+}
+
+#[macro_export]
+macro_rules! try_println {
+    () => ($crate::try_print!("\n"));
+    ($($arg:tt)*) => ($crate::try_print!("{}\n", format_args!($($arg)*)));
+}
+
+/****************************************************************/
+//                           Statics                            //
+/****************************************************************/
+
+struct Consoles {
+    list: [Static; CONSOLES],
+    mode: Mode,
+    status: Option <StatusPosition>,
+    started: bool, //< Whether the active console was drawn yet
+    active: usize, //< Console shown on the screen
+    output: usize  //< Console `print!` writes to
+}
+
+impl Consoles {
+    /* `const` so that the consoles are placed right in the image instead of being built on the stack */
+    const fn new() -> Consoles {
+        const MODE: Mode = Mode::Text80x25;
+        Consoles {
+            list: [Static::new(MODE), Static::new(MODE), Static::new(MODE), Static::new(MODE), Static::new(MODE), Static::new(MODE)],
+            mode: MODE,
+            status: None,
+            started: false,
+            active: 0,
+            output: 0
+        }
+    }
+
+    fn output(&mut self) -> &mut Static {
+        &mut self.list[self.output]
+    }
+
+    fn active(&mut self) -> &mut Static {
+        &mut self.list[self.active]
+    }
+}
+
+static TTY: Mutex <Consoles> = Mutex::new(Consoles::new());
+
+/****************************************************************/
+//                     Other functions                          //
+/****************************************************************/
+
+/* Virtual address of VGA text memory */
+fn buffer_address() -> usize {
+    vga::memory(BUFFER_ADDRESS)
+}
+
+/* Interrupts must be off while the lock is held: the console hotkeys take it from the keyboard IRQ */
+fn consoles() -> MutexGuard <'static, Consoles> {
+    debug_assert!(!interrupts::are_enabled(), "TTY locked with interrupts on");
+    start(TTY.lock())
+}
+
+/* Activates the first console on the first use */
+fn start(mut consoles: MutexGuard <'static, Consoles>) -> MutexGuard <'static, Consoles> {
+    if !consoles.started {
+        consoles.started = true;
+        consoles.active().activate();
+    }
+    consoles
+}
+
+/* Switches VGA to text mode `mode`, consoles keep the lines around their cursors */
+pub fn set_mode(mode: Mode) {
+    interrupts::without_interrupts(|| {
+        let mut consoles = consoles();
+        let old = consoles.mode;
+        if old == mode { return }
+        pointer::around(&mut consoles, |consoles| {
+            unsafe {
+                vga::write_registers(mode.registers());
+                vga::rescale_font(old.char_height(), mode.char_height());
+            }
+            consoles.mode = mode;
+            let status = consoles.status;
+            for tty in consoles.list.iter_mut() { tty.resize(mode, status) }
+            consoles.active().activate();
+            status::draw(consoles);
+        });
+    });
+}
+
+pub fn mode() -> Mode {
+    interrupts::without_interrupts(|| TTY.lock().mode)
+}
+
+/* Columns of the current text mode */
+pub fn width() -> u8 {
+    mode().width()
+}
+
+/* Rows of the current text mode */
+pub fn height() -> u8 {
+    mode().height()
+}
+
+/* Column and row of the cursor of the output console */
+pub fn cursor_position() -> (u8, u8) {
+    interrupts::without_interrupts(|| {
+        let mut tty = consoles();
+        let tty = tty.output();
+        (tty.x, tty.y)
+    })
+}
+
+#[allow(dead_code)]
+pub fn set_color(color: VGA) {
+    interrupts::without_interrupts(|| consoles().output().color = color);
+}
+
+#[allow(dead_code)]
+pub fn get_color() -> VGA {
+    interrupts::without_interrupts(|| consoles().output().color)
+}
+
+pub fn show_cursor() {
+    interrupts::without_interrupts(|| {
+        let mut tty = consoles();
+        let tty = tty.output();
+        tty.cursor_visible = true;
+        tty.update_cursor_shape();
+    });
+}
+
+pub fn hide_cursor() {
+    interrupts::without_interrupts(|| {
+        let mut tty = consoles();
+        let tty = tty.output();
+        tty.cursor_visible = false;
+        tty.update_cursor_shape();
+    });
+}
+
+pub fn set_cursor_shape(shape: CursorShape) {
+    interrupts::without_interrupts(|| {
+        let mut tty = consoles();
+        let tty = tty.output();
+        tty.cursor = shape;
+        tty.update_cursor_shape();
+    });
+}
+
+pub fn get_cursor_shape() -> CursorShape {
+    interrupts::without_interrupts(|| consoles().output().cursor)
+}
+
+/* Sets how many lines evicted from the screen are kept by each console; 0 disables scrollback */
+#[cfg(feature = "allocator")]
+pub fn set_scrollback_depth(depth: usize) {
+    interrupts::without_interrupts(|| {
+        for tty in consoles().list.iter_mut() {
+            tty.view(0);
+            tty.history.set_depth(depth);
+        }
+    });
+}
+
+#[cfg(feature = "allocator")]
+pub fn scroll_view_up(lines: usize) {
+    interrupts::without_interrupts(|| {
+        pointer::around(&mut consoles(), |consoles| {
+            let tty = consoles.active();
+            let offset = tty.history.offset() + lines;
+            tty.view(offset);
+        });
+    });
+}
+
+#[cfg(feature = "allocator")]
+pub fn scroll_view_down(lines: usize) {
+    interrupts::without_interrupts(|| {
+        pointer::around(&mut consoles(), |consoles| {
+            let tty = consoles.active();
+            let offset = tty.history.offset().saturating_sub(lines);
+            tty.view(offset);
+        });
+    });
+}
+
+/* Shows console `console` on the screen */
+pub fn switch_console(console: usize) {
+    if console >= CONSOLES { return }
+    interrupts::without_interrupts(|| {
+        let mut tty = consoles();
+        if tty.active == console { return }
+        pointer::around(&mut tty, |tty| {
+            tty.active().deactivate();
+            tty.active = console;
+            tty.active().activate();
+        });
+    });
+}
+
+pub fn active_console() -> usize {
+    interrupts::without_interrupts(|| consoles().active)
+}
+
+/* Makes `print!` write to console `console` */
+pub fn set_output_console(console: usize) {
+    if console >= CONSOLES { return }
+    interrupts::without_interrupts(|| consoles().output = console);
+}
+
+pub fn output_console() -> usize {
+    interrupts::without_interrupts(|| consoles().output)
+}
+
+/* Writes CP437 bytes as they are, without UTF-8 decoding and escape sequences */
+pub fn write_raw(bytes: &[u8]) {
+    interrupts::without_interrupts(|| {
+        pointer::around(&mut consoles(), |consoles| consoles.output().write_raw(bytes));
+    });
+}
+
+#[doc(hidden)]
+pub fn _print(args: fmt::Arguments) {
+    use fmt::Write;
+
+    interrupts::without_interrupts(|| {
+        pointer::around(&mut consoles(), |consoles| consoles.output().write_fmt(args)).unwrap();
+    });
+
+    #[cfg(feature = "serial")]
+    if crate::serial::mirror() {
+        crate::serial::_print(args);
+    }
+}
+
+/* Output to a console that does not exist is dropped */
+#[doc(hidden)]
+pub fn _print_to(console: usize, args: fmt::Arguments) {
+    use fmt::Write;
+
+    if console >= CONSOLES { return }
+    interrupts::without_interrupts(|| {
+        pointer::around(&mut consoles(), |consoles| consoles.list[console].write_fmt(args)).unwrap();
+    });
+
+    #[cfg(feature = "serial")]
+    if crate::serial::mirror() {
+        crate::serial::_print(args);
+    }
+}
+
+#[doc(hidden)]
+pub fn _try_print(args: fmt::Arguments) {
+    use fmt::Write;
+
+    interrupts::without_interrupts(|| {
+        if let Some(consoles) = TTY.try_lock() {
+            pointer::around(&mut start(consoles), |consoles| consoles.output().write_fmt(args)).ok();
+        }
+    });
+
+    #[cfg(feature = "serial")]
+    if crate::serial::mirror() {
+        crate::serial::_try_print(args);
+    }
+}
@@ -0,0 +1,132 @@
+/****************************************************************/
+//                            Uses                              //
+/****************************************************************/
+
+use spin::Mutex;
+use x86_64::instructions::interrupts;
+use super::{Consoles, VGA, ScreenChar, TTY, buffer_address};
+
+/****************************************************************/
+//                         Constants                            //
+/****************************************************************/
+
+/* Mouse counts per character cell */
+pub const SCALE_X: i32 = 8;
+pub const SCALE_Y: i32 = 16;
+
+/****************************************************************/
+//                            Types                             //
+/****************************************************************/
+
+/* Text-mode mouse cursor: the cell under it is shown with swapped colors */
+struct Pointer {
+    visible: bool,
+    x: i32, //< In mouse counts, see `SCALE_X`
+    y: i32,
+    drawn: Option <(usize, ScreenChar, ScreenChar)> //< Offset, original and inverted cell
+}
+
+impl Pointer {
+    const fn new() -> Self {
+        Pointer {
+            visible: false,
+            x: 0,
+            y: 0,
+            drawn: None
+        }
+    }
+
+    fn cell(&self) -> (u8, u8) {
+        ((self.x / SCALE_X) as u8, (self.y / SCALE_Y) as u8)
+    }
+
+    /* Puts back the cell under the cursor unless the console has overwritten it since */
+    fn erase(&mut self) {
+        if let Some((offset, original, inverted)) = self.drawn.take() {
+            let cell = unsafe { (buffer_address() as *mut ScreenChar).add(offset) };
+            unsafe {
+                if cell.read_volatile() == inverted { cell.write_volatile(original) }
+            }
+        }
+    }
+
+    fn draw(&mut self, consoles: &Consoles) {
+        self.erase();
+        let width = consoles.mode.width() as i32;
+        let height = consoles.mode.height() as i32;
+        self.x = self.x.max(0).min(width * SCALE_X - 1);
+        self.y = self.y.max(0).min(height * SCALE_Y - 1);
+        if !self.visible { return }
+
+        let offset = (self.y / SCALE_Y * width + self.x / SCALE_X) as usize;
+        let cell = unsafe { (buffer_address() as *mut ScreenChar).add(offset) };
+        let original = unsafe { cell.read_volatile() };
+        let color = original.color.0;
+        let inverted = ScreenChar { ascii: original.ascii, color: VGA(color << 4 | color >> 4) };
+        unsafe { cell.write_volatile(inverted) }
+        self.drawn = Some((offset, original, inverted));
+    }
+}
+
+/****************************************************************/
+//                           Statics                            //
+/****************************************************************/
+
+static POINTER: Mutex <Pointer> = Mutex::new(Pointer::new());
+
+/****************************************************************/
+//                     Other functions                          //
+/****************************************************************/
+
+/* The old cell is always put back, since that needs no console lock; the new one is drawn
+   unless the consoles are busy, the next movement catches up then */
+fn update(f: impl FnOnce(&mut Pointer)) {
+    interrupts::without_interrupts(|| {
+        let mut pointer = POINTER.lock();
+        f(&mut pointer);
+        pointer.erase();
+        if let Some(consoles) = TTY.try_lock() { pointer.draw(&consoles) }
+    });
+}
+
+/* Console output scrolls and overwrites the inverted cell, so the cell is put back before `output`
+   runs on the locked consoles and drawn again after it */
+pub(super) fn around <R> (consoles: &mut Consoles, output: impl FnOnce(&mut Consoles) -> R) -> R {
+    let mut pointer = POINTER.try_lock();
+    if let Some(pointer) = pointer.as_mut() { pointer.erase() }
+    let result = output(consoles);
+    if let Some(pointer) = pointer.as_mut() { pointer.draw(consoles) }
+    result
+}
+
+pub fn show_pointer() {
+    update(|pointer| pointer.visible = true)
+}
+
+pub fn hide_pointer() {
+    update(|pointer| pointer.visible = false)
+}
+
+pub fn pointer_visible() -> bool {
+    interrupts::without_interrupts(|| POINTER.lock().visible)
+}
+
+/* Column and row under the cursor */
+pub fn pointer_position() -> (u8, u8) {
+    interrupts::without_interrupts(|| POINTER.lock().cell())
+}
+
+pub fn set_pointer_position(column: u8, row: u8) {
+    update(|pointer| {
+        pointer.x = column as i32 * SCALE_X;
+        pointer.y = row as i32 * SCALE_Y;
+    })
+}
+
+/* Moves by mouse counts, `dy` grows downwards; safe to call from the mouse IRQ */
+pub fn move_pointer(dx: i32, dy: i32) {
+    update(|pointer| {
+        pointer.x += dx;
+        pointer.y += dy;
+    })
+}
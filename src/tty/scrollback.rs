@@ -0,0 +1,101 @@
+#[cfg(feature = "allocator")]
+mod private {
+    /****************************************************************/
+    //                            Uses                              //
+    /****************************************************************/
+
+    use alloc::vec::Vec;
+    use crate::tty::{
+        Line,
+        Screen,
+        ScreenChar,
+        Buffer,
+        BLANK,
+        MAX_WIDTH
+    };
+
+    /****************************************************************/
+    //                         Constants                            //
+    /****************************************************************/
+
+    /* Each line takes 180 bytes of the heap and every console has its own scrollback, so keep it modest */
+    pub const DEFAULT_SCROLLBACK_DEPTH: usize = 50;
+
+    /****************************************************************/
+    //                            Types                             //
+    /****************************************************************/
+
+    /* Ring of lines evicted from the top of the screen */
+    pub struct Scrollback {
+        lines: Vec <Line>,
+        head: usize,  //< Index of the oldest line once the ring is full
+        depth: usize,
+        offset: usize //< How many lines the view is moved up; 0 - live view
+    }
+
+    impl Scrollback {
+        pub const fn new() -> Self {
+            Scrollback {
+                lines: Vec::new(),
+                head: 0,
+                depth: 0,
+                offset: 0
+            }
+        }
+
+        /* Returns `n`-th line counting from the oldest one */
+        fn line(&self, n: usize) -> &Line {
+            &self.lines[(self.head + n) % self.lines.len()]
+        }
+
+        pub fn set_depth(&mut self, depth: usize) {
+            let keep = self.lines.len().min(depth);
+            let mut lines = Vec::with_capacity(keep);
+            for n in self.lines.len() - keep..self.lines.len() { lines.push(*self.line(n)) }
+            self.lines = lines;
+            self.head = 0;
+            self.depth = depth;
+            self.offset = self.offset.min(self.lines.len());
+        }
+
+        /* Saves line, `line.len()` is the width of the console */
+        pub fn push(&mut self, line: &[ScreenChar]) {
+            if self.depth == 0 { return }
+            let mut saved = [BLANK; MAX_WIDTH as usize];
+            saved[..line.len()].copy_from_slice(line);
+            if self.lines.len() < self.depth {
+                self.lines.push(saved);
+            } else {
+                self.lines[self.head] = saved;
+                self.head = (self.head + 1) % self.depth;
+            }
+        }
+
+        pub fn offset(&self) -> usize {
+            self.offset
+        }
+
+        pub fn is_scrolled(&self) -> bool {
+            self.offset != 0
+        }
+
+        /* Shows `screen` moved `offset` lines up into history */
+        pub fn view(&mut self, buffer: &mut Buffer, screen: &Screen, width: usize, height: usize, offset: usize) {
+            let offset = offset.min(self.lines.len());
+            if offset == self.offset { return }
+            self.offset = offset;
+
+            let history = self.lines.len();
+            for y in 0..height {
+                let index = history - offset + y;
+                let line = if index < history { &self.line(index)[..width] } else { &screen[(index - history) * width..(index - history + 1) * width] };
+                for (x, &c) in line.iter().enumerate() {
+                    buffer.chars[y * width + x].write(c);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "allocator")]
+pub use private::*;
@@ -0,0 +1,195 @@
+/****************************************************************/
+//                            Uses                              //
+/****************************************************************/
+
+use core::{
+    fmt,
+    sync::atomic::{AtomicU64, Ordering}
+};
+use spin::Mutex;
+use x86_64::instructions::interrupts;
+use super::{Consoles, Color, VGA, ScreenChar, TTY, buffer_address, cp437};
+
+/****************************************************************/
+//                         Constants                            //
+/****************************************************************/
+
+pub const MAX_FIELDS: usize = 8;
+
+/* Longest text of a field, including its name */
+pub const FIELD_LENGTH: usize = 24;
+
+/* Timer ticks between redraws without the `time` feature, when the timer runs at the BIOS rate of 18.2 Hz */
+pub const REFRESH_TICKS: u64 = 9;
+
+const COLOR: VGA = VGA::make(Color::Black, Color::LightGray);
+
+const SEPARATOR: &str = " | ";
+
+/****************************************************************/
+//                            Types                             //
+/****************************************************************/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum StatusPosition {
+    Top,
+    Bottom
+}
+
+/* `name: text`, already encoded to CP437 */
+#[derive(Clone, Copy)]
+struct Field {
+    name: &'static str,
+    text: [u8; FIELD_LENGTH],
+    len: usize
+}
+
+impl Field {
+    fn new(name: &'static str) -> Self {
+        let mut field = Field {
+            name,
+            text: [0; FIELD_LENGTH],
+            len: 0
+        };
+        fmt::Write::write_fmt(&mut field, format_args!("{}: ", name)).ok();
+        field
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.text[..self.len]
+    }
+}
+
+/* Text that does not fit is cut */
+impl fmt::Write for Field {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            if self.len == FIELD_LENGTH { break }
+            self.text[self.len] = cp437::encode(c).unwrap_or(cp437::REPLACEMENT);
+            self.len += 1;
+        }
+        Ok(())
+    }
+}
+
+/****************************************************************/
+//                           Statics                            //
+/****************************************************************/
+
+static FIELDS: Mutex <[Option <Field>; MAX_FIELDS]> = Mutex::new([None; MAX_FIELDS]);
+
+static TICKS: AtomicU64 = AtomicU64::new(0);
+
+/****************************************************************/
+//                     Other functions                          //
+/****************************************************************/
+
+/* Draws fields into the reserved row, called with the consoles locked */
+pub(super) fn draw(consoles: &Consoles) {
+    let position = match consoles.status {
+        Some(position) => position,
+        None => return
+    };
+    let fields = match FIELDS.try_lock() {
+        Some(fields) => *fields,
+        None => return
+    };
+    let width = consoles.mode.width() as usize;
+    let row = match position {
+        StatusPosition::Top => 0,
+        StatusPosition::Bottom => consoles.mode.height() as usize - 1
+    };
+
+    let line = fields.iter().flatten().enumerate().flat_map(|(i, field)| {
+        let separator = if i == 0 { &b" "[..] } else { SEPARATOR.as_bytes() };
+        separator.iter().chain(field.as_bytes().iter())
+    }).copied().chain(core::iter::repeat(b' ')).take(width);
+
+    let buffer = buffer_address() as *mut ScreenChar;
+    for (x, ascii) in line.enumerate() {
+        unsafe { buffer.add(row * width + x).write_volatile(ScreenChar { ascii, color: COLOR }) }
+    }
+}
+
+/* Reserves the top or the bottom row for the status line, `None` gives it back to the consoles */
+pub fn set_status_line(position: Option <StatusPosition>) {
+    interrupts::without_interrupts(|| {
+        let mut consoles = super::consoles();
+        if consoles.status == position { return }
+        consoles.status = position;
+        let mode = consoles.mode;
+        for tty in consoles.list.iter_mut() { tty.resize(mode, position) }
+        consoles.active().activate();
+        draw(&consoles);
+    });
+}
+
+pub fn status_line() -> Option <StatusPosition> {
+    interrupts::without_interrupts(|| TTY.lock().status)
+}
+
+/* Publishes field `name`, it is shown on the next refresh */
+pub fn set_field(name: &'static str, args: fmt::Arguments) {
+    let mut field = Field::new(name);
+    fmt::Write::write_fmt(&mut field, args).ok();
+    interrupts::without_interrupts(|| {
+        let mut fields = FIELDS.lock();
+        let slot = fields.iter().position(|f| matches!(f, Some(f) if f.name == name)).or_else(|| fields.iter().position(Option::is_none));
+        if let Some(slot) = slot { fields[slot] = Some(field) }
+    });
+}
+
+pub fn remove_field(name: &'static str) {
+    interrupts::without_interrupts(|| {
+        for field in FIELDS.lock().iter_mut() {
+            if matches!(field, Some(f) if f.name == name) { *field = None }
+        }
+    });
+}
+
+/* Redraws the status line unless the consoles are busy */
+pub fn refresh() {
+    interrupts::without_interrupts(|| {
+        if let Some(mut consoles) = TTY.try_lock() { super::pointer::around(&mut consoles, |consoles| draw(consoles)) }
+    });
+}
+
+/* Fields the kernel publishes by itself */
+fn publish() {
+    #[cfg(feature = "time")] {
+        let seconds = crate::time::Microseconds::from(crate::time::now()) / 1_000_000;
+        set_field("up", format_args!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60));
+    }
+
+    #[cfg(feature = "allocator")]
+    set_field("heap", format_args!("{}/{}K", crate::allocator::used() / 1024, crate::allocator::HEAP_SIZE / 1024));
+
+    #[cfg(all(feature = "keyboard", feature = "allocator"))]
+    set_field("caps", format_args!("{}", if crate::keyboard::caps() { "on" } else { "off" }));
+
+    #[cfg(all(feature = "oll", feature = "hash", feature = "keyboard"))]
+    set_field("debug", format_args!("{}", if crate::oll::is_debug_mode_on() { "on" } else { "off" }));
+}
+
+/* Ticks in half a second at the programmed timer rate */
+#[cfg(feature = "time")]
+fn refresh_ticks() -> u64 {
+    (crate::time::frequency() as u64 / 2).max(1)
+}
+
+#[cfg(not(feature = "time"))]
+fn refresh_ticks() -> u64 {
+    REFRESH_TICKS
+}
+
+/* Called from the timer IRQ */
+pub fn timer_isr() {
+    if TICKS.fetch_add(1, Ordering::Relaxed) % refresh_ticks() != 0 { return }
+    match TTY.try_lock() {
+        Some(consoles) if consoles.status.is_some() => { },
+        _ => return
+    }
+    publish();
+    refresh();
+}
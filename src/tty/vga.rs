@@ -0,0 +1,237 @@
+/****************************************************************/
+//                            Uses                              //
+/****************************************************************/
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use x86_64::instructions::port::Port;
+
+/****************************************************************/
+//                         Constants                            //
+/****************************************************************/
+
+pub const MISC_WRITE_PORT: u16 = 0x3C2;
+pub const SEQUENCER_PORT: u16 = 0x3C4;
+pub const GRAPHICS_PORT: u16 = 0x3CE;
+pub const ATTRIBUTE_PORT: u16 = 0x3C0;
+pub const CRTC_PORT: u16 = 0x3D4;
+pub const INPUT_STATUS_PORT: u16 = 0x3DA;
+
+/* Plane 2 holds the character generator RAM */
+pub const FONT_MEMORY: usize = 0xA0000;
+
+/* VGA text mode shows at most this many glyphs */
+pub const GLYPHS: usize = 256;
+
+/* Each glyph takes 32 bytes in plane 2 regardless of its height */
+pub const GLYPH_SLOT: usize = 32;
+
+/****************************************************************/
+//                            Types                             //
+/****************************************************************/
+
+/* Values of all the registers that define a video mode */
+pub struct Registers {
+    pub misc: u8,
+    pub sequencer: [u8; 5],
+    pub crtc: [u8; 25],
+    pub graphics: [u8; 9],
+    pub attribute: [u8; 21]
+}
+
+/****************************************************************/
+//                           Statics                            //
+/****************************************************************/
+
+pub static TEXT_80X25: Registers = Registers {
+    misc: 0x67,
+    sequencer: [0x03, 0x00, 0x03, 0x00, 0x02],
+    crtc: [
+        0x5F, 0x4F, 0x50, 0x82, 0x55, 0x81, 0xBF, 0x1F,
+        0x00, 0x4F, 0x0D, 0x0E, 0x00, 0x00, 0x00, 0x50,
+        0x9C, 0x0E, 0x8F, 0x28, 0x1F, 0x96, 0xB9, 0xA3,
+        0xFF
+    ],
+    graphics: [0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0E, 0x00, 0xFF],
+    attribute: [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x14, 0x07,
+        0x38, 0x39, 0x3A, 0x3B, 0x3C, 0x3D, 0x3E, 0x3F,
+        0x0C, 0x00, 0x0F, 0x08, 0x00
+    ]
+};
+
+pub static TEXT_80X50: Registers = Registers {
+    misc: 0x67,
+    sequencer: [0x03, 0x00, 0x03, 0x00, 0x02],
+    crtc: [
+        0x5F, 0x4F, 0x50, 0x82, 0x55, 0x81, 0xBF, 0x1F,
+        0x00, 0x47, 0x06, 0x07, 0x00, 0x00, 0x01, 0x40,
+        0x9C, 0x8E, 0x8F, 0x28, 0x1F, 0x96, 0xB9, 0xA3,
+        0xFF
+    ],
+    graphics: [0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0E, 0x00, 0xFF],
+    attribute: [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x14, 0x07,
+        0x38, 0x39, 0x3A, 0x3B, 0x3C, 0x3D, 0x3E, 0x3F,
+        0x0C, 0x00, 0x0F, 0x08, 0x00
+    ]
+};
+
+pub static TEXT_90X30: Registers = Registers {
+    misc: 0xE7,
+    sequencer: [0x03, 0x01, 0x03, 0x00, 0x02],
+    crtc: [
+        0x6B, 0x59, 0x5A, 0x82, 0x60, 0x8D, 0x0B, 0x3E,
+        0x00, 0x4F, 0x0D, 0x0E, 0x00, 0x00, 0x00, 0x00,
+        0xEA, 0x0C, 0xDF, 0x2D, 0x10, 0xE8, 0x05, 0xA3,
+        0xFF
+    ],
+    graphics: [0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0E, 0x00, 0xFF],
+    attribute: [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x14, 0x07,
+        0x38, 0x39, 0x3A, 0x3B, 0x3C, 0x3D, 0x3E, 0x3F,
+        0x0C, 0x00, 0x0F, 0x08, 0x00
+    ]
+};
+
+pub static TEXT_90X60: Registers = Registers {
+    misc: 0xE7,
+    sequencer: [0x03, 0x01, 0x03, 0x00, 0x02],
+    crtc: [
+        0x6B, 0x59, 0x5A, 0x82, 0x60, 0x8D, 0x0B, 0x3E,
+        0x00, 0x47, 0x06, 0x07, 0x00, 0x00, 0x00, 0x00,
+        0xEA, 0x0C, 0xDF, 0x2D, 0x08, 0xE8, 0x05, 0xA3,
+        0xFF
+    ],
+    graphics: [0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0E, 0x00, 0xFF],
+    attribute: [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x14, 0x07,
+        0x38, 0x39, 0x3A, 0x3B, 0x3C, 0x3D, 0x3E, 0x3F,
+        0x0C, 0x00, 0x0F, 0x08, 0x00
+    ]
+};
+
+/* 16-line glyphs replaced by `rescale_font`, to bring them back later */
+static mut SAVED_FONT: [u8; GLYPHS * 16] = [0; GLYPHS * 16];
+static mut SAVED_FONT_VALID: bool = false;
+
+/* Where the bootloader mapped physical memory; until `init` only the identity-mapped text page is usable */
+static PHYSICAL_MEMORY_OFFSET: AtomicUsize = AtomicUsize::new(0);
+
+/****************************************************************/
+//                     Other functions                          //
+/****************************************************************/
+
+/* Takes `BootInfo::physical_memory_offset`, VGA memory is reached through it from then on */
+pub fn init(physical_memory_offset: u64) {
+    PHYSICAL_MEMORY_OFFSET.store(physical_memory_offset as usize, Ordering::Relaxed);
+}
+
+/* Virtual address of VGA memory at `physical` */
+pub fn memory(physical: usize) -> usize {
+    PHYSICAL_MEMORY_OFFSET.load(Ordering::Relaxed) + physical
+}
+
+pub unsafe fn write_register(port: u16, index: u8, value: u8) {
+    Port::new(port).write(index);
+    Port::new(port + 1).write(value);
+}
+
+pub unsafe fn read_register(port: u16, index: u8) -> u8 {
+    Port::new(port).write(index);
+    Port::new(port + 1).read()
+}
+
+pub unsafe fn write_registers(regs: &Registers) {
+    Port::new(MISC_WRITE_PORT).write(regs.misc);
+
+    for (i, &value) in regs.sequencer.iter().enumerate() {
+        write_register(SEQUENCER_PORT, i as u8, value);
+    }
+
+    /* Unlock CRTC registers 0..8 and keep them unlocked */
+    write_register(CRTC_PORT, 0x03, read_register(CRTC_PORT, 0x03) | 0x80);
+    write_register(CRTC_PORT, 0x11, read_register(CRTC_PORT, 0x11) & !0x80);
+    for (i, &value) in regs.crtc.iter().enumerate() {
+        let value = match i {
+            0x03 => value | 0x80,
+            0x11 => value & !0x80,
+            _ => value
+        };
+        write_register(CRTC_PORT, i as u8, value);
+    }
+
+    for (i, &value) in regs.graphics.iter().enumerate() {
+        write_register(GRAPHICS_PORT, i as u8, value);
+    }
+
+    /* Attribute controller has single port for index and data, reading the status resets the flip-flop to index */
+    for (i, &value) in regs.attribute.iter().enumerate() {
+        Port::<u8>::new(INPUT_STATUS_PORT).read();
+        Port::new(ATTRIBUTE_PORT).write(i as u8);
+        Port::new(ATTRIBUTE_PORT).write(value);
+    }
+    Port::<u8>::new(INPUT_STATUS_PORT).read();
+    Port::new(ATTRIBUTE_PORT).write(0x20u8);
+}
+
+/* Gives CPU access to plane 2 only, flat addressing at `FONT_MEMORY` */
+pub unsafe fn map_font_memory() {
+    write_register(SEQUENCER_PORT, 0x02, 0x04);
+    write_register(SEQUENCER_PORT, 0x04, 0x07);
+    write_register(GRAPHICS_PORT, 0x04, 0x02);
+    write_register(GRAPHICS_PORT, 0x05, 0x00);
+    write_register(GRAPHICS_PORT, 0x06, 0x04);
+}
+
+/* Back to text mode: planes 0 and 1, odd/even addressing at 0xB8000 */
+pub unsafe fn unmap_font_memory() {
+    write_register(SEQUENCER_PORT, 0x02, 0x03);
+    write_register(SEQUENCER_PORT, 0x04, 0x03);
+    write_register(GRAPHICS_PORT, 0x04, 0x00);
+    write_register(GRAPHICS_PORT, 0x05, 0x10);
+    write_register(GRAPHICS_PORT, 0x06, 0x0E);
+}
+
+/* Font memory must be mapped and `init` called */
+pub unsafe fn read_glyph(index: usize, rows: &mut [u8]) {
+    let slot = (memory(FONT_MEMORY) + index * GLYPH_SLOT) as *const u8;
+    for (row, dst) in rows.iter_mut().enumerate().take(GLYPH_SLOT) {
+        *dst = slot.add(row).read_volatile();
+    }
+}
+
+/* Font memory must be mapped and `init` called */
+pub unsafe fn write_glyph(index: usize, rows: &[u8]) {
+    let slot = (memory(FONT_MEMORY) + index * GLYPH_SLOT) as *mut u8;
+    for row in 0..GLYPH_SLOT {
+        slot.add(row).write_volatile(if row < rows.len() { rows[row] } else { 0 });
+    }
+}
+
+/* Converts glyphs in plane 2 between 16 and 8 scanlines per character */
+pub unsafe fn rescale_font(from: u8, to: u8) {
+    if from == to { return }
+    map_font_memory();
+    let mut rows = [0u8; 16];
+    for index in 0..GLYPHS {
+        if from == 16 && to == 8 {
+            read_glyph(index, &mut rows);
+            SAVED_FONT[index * 16..(index + 1) * 16].copy_from_slice(&rows);
+            /* OR row pairs so thin strokes do not disappear */
+            let mut half = [0u8; 8];
+            for (i, row) in half.iter_mut().enumerate() { *row = rows[2 * i] | rows[2 * i + 1] }
+            write_glyph(index, &half);
+        } else if from == 8 && to == 16 {
+            if SAVED_FONT_VALID {
+                write_glyph(index, &SAVED_FONT[index * 16..(index + 1) * 16]);
+            } else {
+                read_glyph(index, &mut rows[..8]);
+                let mut double = [0u8; 16];
+                for (i, row) in double.iter_mut().enumerate() { *row = rows[i / 2] }
+                write_glyph(index, &double);
+            }
+        }
+    }
+    SAVED_FONT_VALID = from == 16 && to == 8;
+    unmap_font_memory();
+}